@@ -0,0 +1,5 @@
+fn main() {
+    // `vulkano_shaders::shader!` reads the GLSL files at compile time, but cargo
+    // doesn't know about them, so editing a shader wouldn't trigger a rebuild.
+    println!("cargo:rerun-if-changed=src/shaders");
+}
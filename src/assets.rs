@@ -0,0 +1,162 @@
+// Build-in modules
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::env;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+// External modules
+use image::Rgba;
+use image::RgbaImage;
+use vulkano::device::Queue;
+use vulkano::format::Format;
+use vulkano::image::Dimensions;
+use vulkano::image::ImmutableImage;
+use vulkano::sync::GpuFuture;
+
+// Internal modules
+use crate::error::AppError;
+use crate::scene::UploadFuture;
+
+/// Overrides where the assets are looked for
+const ASSETS_DIR_VAR: &str = "ASSETS_DIR";
+
+/// Name of the directory the assets are shipped in
+const ASSETS_DIR_NAME: &str = "assets";
+
+/// Side in pixels of the checkerboard standing in for images that can't be
+/// loaded, and of each of its squares
+const MISSING_IMAGE_SIZE: u32 = 64;
+const MISSING_SQUARE_SIZE: u32 = 8;
+
+/// Colors the squares of that checkerboard alternate between, which no real
+/// texture is likely to be made of
+const MISSING_COLORS: [[u8; 4]; 2] = [[255, 0, 255, 255], [0, 0, 0, 255]];
+
+/// Finds the files the program needs, and keeps what was loaded from them so
+/// that each is only loaded once
+pub struct Assets {
+    root: PathBuf,
+    textures: RefCell<HashMap<String, Arc<ImmutableImage<Format>>>>,
+    /// Texture uploads nothing has waited for yet
+    pending_uploads: RefCell<Option<UploadFuture>>,
+}
+
+impl Assets {
+    /// Looks for the assets in `$ASSETS_DIR` when it's set, or else in the
+    /// first `assets` directory found next to the executable or above it,
+    /// which covers both installed copies and `cargo run`. Falls back to the
+    /// working directory's.
+    pub fn new() -> Self {
+        let root =
+            env::var_os(ASSETS_DIR_VAR)
+                .map(PathBuf::from)
+                .or_else(|| {
+                    let exe = env::current_exe().ok()?;
+                    exe.ancestors()
+                        .skip(1)
+                        .map(|dir| dir.join(ASSETS_DIR_NAME))
+                        .find(|dir| dir.is_dir())
+                })
+                .unwrap_or_else(|| PathBuf::from(ASSETS_DIR_NAME));
+
+        Assets {
+            root,
+            textures: RefCell::new(HashMap::new()),
+            pending_uploads: RefCell::new(None),
+        }
+    }
+
+    /// Where the asset called `name` is
+    pub fn path(&self, name: &str) -> PathBuf {
+        self.root.join(name)
+    }
+
+    /// Returns the PNG called `name` as an sRGB image, uploading it through
+    /// `queue` the first time it's asked for. Until `take_pending_uploads`
+    /// is waited on, the upload may not be done.
+    pub fn load_texture(&self, queue: Arc<Queue>, name: &str)
+        -> Result<Arc<ImmutableImage<Format>>, AppError>
+    {
+        self.load_image(queue, name, Format::R8G8B8A8Srgb)
+    }
+
+    /// Same as `load_texture` for a tangent space normal map: its channels hold
+    /// the coordinates of directions, read back linearly rather than decoded
+    /// like colors
+    pub fn load_normal_map(&self, queue: Arc<Queue>, name: &str)
+        -> Result<Arc<ImmutableImage<Format>>, AppError>
+    {
+        self.load_image(queue, name, Format::R8G8B8A8Unorm)
+    }
+
+    fn load_image(&self, queue: Arc<Queue>, name: &str, format: Format)
+        -> Result<Arc<ImmutableImage<Format>>, AppError>
+    {
+        if let Some(texture) = self.textures.borrow().get(name) {
+            return Ok(texture.clone());
+        }
+
+        let (texture, upload) = upload_image(queue, &self.path(name), format)?;
+
+        let mut pending_uploads = self.pending_uploads.borrow_mut();
+        *pending_uploads = Some(match pending_uploads.take() {
+            Some(previous) => Box::new(previous.join(upload)),
+            None => upload,
+        });
+
+        self.textures.borrow_mut().insert(name.to_string(), texture.clone());
+
+        Ok(texture)
+    }
+
+    /// Returns the uploads of the textures loaded so far, once
+    pub fn take_pending_uploads(&self) -> Option<UploadFuture> {
+        self.pending_uploads.borrow_mut().take()
+    }
+}
+
+/// Loads the PNG at `path` into a new image of `format`, returning it along
+/// with its upload through `queue`, which nothing has submitted yet. A file
+/// missing or which can't be decoded gets a checkerboard instead, with a
+/// warning, so that it shows without stopping the program.
+pub fn upload_image(queue: Arc<Queue>, path: &Path, format: Format)
+    -> Result<(Arc<ImmutableImage<Format>>, UploadFuture), AppError>
+{
+    let pixels = match image::open(path) {
+        Ok(image) => image.to_rgba(),
+        Err(err) => {
+            println!("Warning: could not load {}: {}, drawing a checkerboard instead", path.display(), err);
+            missing_image()
+        },
+    };
+
+    upload_pixels(queue, pixels, format)
+}
+
+/// The magenta and black checkerboard drawn in place of images which can't be
+/// loaded
+fn missing_image() -> RgbaImage {
+    RgbaImage::from_fn(MISSING_IMAGE_SIZE, MISSING_IMAGE_SIZE, |x, y| {
+        let square = (x / MISSING_SQUARE_SIZE + y / MISSING_SQUARE_SIZE) % 2;
+        Rgba(MISSING_COLORS[square as usize])
+    })
+}
+
+/// Same as `upload_image` with pixels already decoded, such as those of an
+/// image embedded in a model
+pub fn upload_pixels(queue: Arc<Queue>, image: RgbaImage, format: Format)
+    -> Result<(Arc<ImmutableImage<Format>>, UploadFuture), AppError>
+{
+    let (width, height) = image.dimensions();
+
+    let (texture, upload) =
+        ImmutableImage::from_iter(
+            image.into_raw().into_iter(),
+            Dimensions::Dim2d { width, height },
+            format,
+            queue
+        )?;
+
+    Ok((texture, Box::new(upload)))
+}
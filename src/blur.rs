@@ -0,0 +1,112 @@
+// Build-in modules
+use std::sync::Arc;
+
+// External modules
+use image::{ImageBuffer, Rgba};
+use vulkano::buffer::BufferUsage;
+use vulkano::buffer::CpuAccessibleBuffer;
+use vulkano::command_buffer::AutoCommandBufferBuilder;
+use vulkano::command_buffer::CommandBuffer;
+use vulkano::descriptor::descriptor_set::PersistentDescriptorSet;
+use vulkano::device::DeviceExtensions;
+use vulkano::format::Format;
+use vulkano::image::Dimensions;
+use vulkano::image::StorageImage;
+use vulkano::instance::InstanceExtensions;
+use vulkano::pipeline::ComputePipeline;
+use vulkano::sync::GpuFuture;
+
+// Internal modules
+use crate::config::Config;
+use crate::error::AppError;
+use crate::renderer::VulkanContext;
+
+/// Where the blurred image is written to
+const OUTPUT_PATH: &str = "blurred.png";
+
+/// Width and height of the pixel blocks the shader works on
+const GROUP_SIZE: u32 = 8;
+
+mod cs {
+    vulkano_shaders::shader!{
+        ty: "compute",
+        path: "src/shaders/blur.comp"
+    }
+}
+
+/// Blurs the image at `path` over `radius` pixels with a compute shader and
+/// saves the result as a PNG, without any window, on the device `config`
+/// prefers
+pub fn run(config: &Config, path: &str, radius: u32) -> Result<(), AppError> {
+    let pixels = image::open(path)?.to_rgba();
+    let (width, height) = pixels.dimensions();
+
+    let context = VulkanContext::new(InstanceExtensions::none())?;
+    let (device, queue, _) = context.open_device(config, &DeviceExtensions::none())?;
+
+    // The horizontal pass reads the image and writes the intermediate one, the
+    // vertical pass reads that back and writes the image again. The pixels
+    // are blurred as they're stored, sRGB encoded.
+    let create_image = || {
+        StorageImage::new(
+            device.clone(),
+            Dimensions::Dim2d { width, height },
+            Format::R8G8B8A8Unorm,
+            Some(queue.family())
+        )
+    };
+    let image = create_image()?;
+    let intermediate = create_image()?;
+
+    let shader = cs::Shader::load(device.clone())?;
+    let pipeline =
+        Arc::new(ComputePipeline::new(device.clone(), &shader.main_entry_point(), &())?);
+
+    let horizontal_set =
+        Arc::new(
+            PersistentDescriptorSet::start(pipeline.clone(), 0)
+                .add_image(image.clone())?
+                .add_image(intermediate.clone())?
+                .build()?
+        );
+    let vertical_set =
+        Arc::new(
+            PersistentDescriptorSet::start(pipeline.clone(), 0)
+                .add_image(intermediate.clone())?
+                .add_image(image.clone())?
+                .build()?
+        );
+
+    let buffer =
+        CpuAccessibleBuffer::from_iter(device.clone(), BufferUsage::all(), pixels.into_raw().into_iter())?;
+
+    // vulkano puts a barrier between both dispatches, the second one reading
+    // what the first one wrote
+    let group_count = [width.div_ceil(GROUP_SIZE), height.div_ceil(GROUP_SIZE), 1];
+    let command_buffer =
+        AutoCommandBufferBuilder::new(device.clone(), queue.family())?
+            .copy_buffer_to_image(buffer.clone(), image.clone())?
+            .dispatch(
+                group_count, pipeline.clone(), horizontal_set,
+                cs::ty::PushConstants { direction: [1, 0], radius: radius as i32 }
+            )?
+            .dispatch(
+                group_count, pipeline.clone(), vertical_set,
+                cs::ty::PushConstants { direction: [0, 1], radius: radius as i32 }
+            )?
+            .copy_image_to_buffer(image.clone(), buffer.clone())?
+            .build()?;
+
+    command_buffer.execute(queue.clone())?
+        .then_signal_fence_and_flush()?
+        .wait(None)?;
+
+    let buffer_content = buffer.read()?;
+    let blurred = ImageBuffer::<Rgba<u8>, _>::from_raw(width, height, &buffer_content[..])
+        .ok_or(AppError::InvalidImageData("buffer is too small for the image"))?;
+
+    blurred.save(OUTPUT_PATH)?;
+    println!("Saved {}", OUTPUT_PATH);
+
+    Ok(())
+}
@@ -0,0 +1,78 @@
+// Build-in modules
+use std::collections::HashSet;
+
+// External modules
+use cgmath::{Deg, Rad};
+use winit::VirtualKeyCode;
+
+/// Camera speed, in units per second
+const CAMERA_SPEED: f32 = 1.0;
+
+/// Looking straight up or down would make the up vector parallel to the
+/// view direction, there would be no way to tell where up is
+const MAX_PITCH: Deg<f32> = Deg(89.0);
+
+/// A free flying camera, turned by the mouse and moved by the keyboard
+pub struct Camera {
+    pub position: [f32; 3],
+    /// Turn around the Y axis, 0 looking towards -Z and growing to the right
+    yaw: Rad<f32>,
+    /// Turn up from the horizon, within `MAX_PITCH`
+    pitch: Rad<f32>,
+    /// How far the mouse moving by a pixel turns the camera
+    sensitivity: Deg<f32>,
+}
+
+impl Camera {
+    /// A camera at `position` looking towards -Z, turning by `sensitivity`
+    /// per pixel of mouse motion
+    pub fn new(position: [f32; 3], sensitivity: Deg<f32>) -> Self {
+        Camera {
+            position,
+            yaw: Rad(0.0),
+            pitch: Rad(0.0),
+            sensitivity,
+        }
+    }
+
+    /// Turns the camera by the mouse moving by `dx` pixels to the right and
+    /// `dy` pixels down
+    pub fn rotate(&mut self, dx: f64, dy: f64) {
+        self.yaw += Rad::from(self.sensitivity * dx as f32);
+        self.pitch -= Rad::from(self.sensitivity * dy as f32);
+
+        let max_pitch = Rad::from(MAX_PITCH);
+        self.pitch = Rad(self.pitch.0.max(-max_pitch.0).min(max_pitch.0));
+    }
+
+    /// The unit vector the camera looks along
+    pub fn forward(&self) -> [f32; 3] {
+        let (sin_yaw, cos_yaw) = self.yaw.0.sin_cos();
+        let (sin_pitch, cos_pitch) = self.pitch.0.sin_cos();
+
+        [cos_pitch * sin_yaw, sin_pitch, -cos_pitch * cos_yaw]
+    }
+
+    /// Moves the camera forward and backward (W/S) or sideways (A/D) in the
+    /// direction it's turned to, staying level, for every held key, so that
+    /// it moves by the same distance per second at any frame rate
+    pub fn translate(&mut self, pressed_keys: &HashSet<VirtualKeyCode>, delta_time: f32) {
+        let distance = CAMERA_SPEED * delta_time;
+        let (sin_yaw, cos_yaw) = self.yaw.0.sin_cos();
+        let forward = [sin_yaw, -cos_yaw];
+        let right = [cos_yaw, sin_yaw];
+
+        for key in pressed_keys {
+            let [x, z] = match key {
+                VirtualKeyCode::W => forward,
+                VirtualKeyCode::S => [-forward[0], -forward[1]],
+                VirtualKeyCode::D => right,
+                VirtualKeyCode::A => [-right[0], -right[1]],
+                _ => continue,
+            };
+
+            self.position[0] += x * distance;
+            self.position[2] += z * distance;
+        }
+    }
+}
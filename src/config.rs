@@ -0,0 +1,485 @@
+// Build-in modules
+use std::env;
+
+// External modules
+use vulkano::pipeline::depth_stencil::Compare;
+use vulkano::swapchain::PresentMode;
+
+// Internal modules
+use crate::error::AppError;
+use crate::scene::Geometry;
+
+/// Printed along with any invalid argument error
+pub const USAGE: &str =
+    "Usage: testing-rust [--help] [--mandelbrot] [--verify] [--headless FRAMES] [--blur-image PATH] \
+     [--two-windows] [--info] [--gradient] [--deferred] [--vertices COUNT] [--obj PATH] [--gltf PATH] \
+     [--translucent] [--overlapping] [--instances COUNT] [--rainbow] [--objects COUNT] [--show-normals] \
+     [--show-axes] [--wave] [--gpu-wave] [--blur RADIUS] [--msaa SAMPLES] [--aniso SAMPLES] [--ortho] \
+     [--stencil] [--depth-func FUNC] [--skybox FACES] [--histogram] [--secondary] \
+     [--record-threads COUNT] [--particles COUNT] [--clear R,G,B] [--device discrete|integrated] \
+     [--gpu INDEX] [--choose-gpu] [--width WIDTH] [--height HEIGHT] [--title TITLE] [--monitor INDEX] \
+     [--vsync] [--colorspace linear|srgb] [--images COUNT] [--max-fps FPS] [--uncapped] [--reactive] \
+     [--profile PATH]";
+
+/// Printed by `--help`
+pub const HELP: &str = "\
+Draws a textured quad, or the chosen geometry, in a window
+
+Modes:
+    --mandelbrot            Render the Mandelbrot set into mandelbrot.png and exit
+    --verify                With --mandelbrot, check a few pixels against the CPU and exit
+                            with an error if any differs
+    --headless FRAMES       Render FRAMES frames offscreen into frame_NNNN.png and exit
+    --blur-image PATH       Gaussian blur the image at PATH over the --blur radius, or 4
+                            pixels, into blurred.png and exit
+    --two-windows           Draw the scene into two windows, without any camera control
+    --info                  Print the formats, present modes and other capabilities of the
+                            window surface, then exit
+    --gradient              Fill the window with a gradient a compute shader writes, blitted
+                            into the swapchain images
+    --deferred              Light a spinning cube with deferred shading: a first subpass
+                            fills a G-buffer the second reads as input attachments
+
+Geometry, the quad by default:
+    --obj PATH              Load the Wavefront OBJ mesh at PATH
+    --gltf PATH             Load the first mesh of the glTF model at PATH, textured with
+                            its base color
+    --translucent           Two overlapping half transparent triangles
+    --overlapping           Two opaque triangles crossing each other, to compare the
+                            --depth-func tests with
+    --vertices COUNT        A grid of about COUNT vertices
+    --instances COUNT       Draw COUNT copies side by side, tinted along a gradient
+                            [default: 1]
+    --rainbow               Tint each copy with a random hue instead
+    --objects COUNT         Draw COUNT copies around a ring, one draw call each rather
+                            than instanced [default: 1]
+
+Drawing:
+    --show-normals          Draw the vertex normals as lines
+    --show-axes             Draw the world axes and the unit cube over the scene
+    --wave                  Animate the vertices along a wave, on the CPU
+    --gpu-wave              Same wave, computed on the GPU
+    --ortho                 Project the geometry onto the window pixels, without perspective
+    --stencil               Only draw the geometry where a disc was drawn into the stencil
+    --depth-func FUNC       Keep the fragments whose depth is less, lequal (less or equal) or
+                            always whatever it is, or greater with the depth range reversed,
+                            which is reverse-Z: the same picture as less with a more even
+                            precision [default: less]
+    --skybox FACES          Surround the scene with a cubemap, from six comma separated
+                            square PNGs of the same size: +X,-X,+Y,-Y,+Z,-Z
+    --histogram             Count the scene pixels by luminance on the GPU and print it
+                            every second, or every frame with --headless. Not with
+                            --two-windows
+    --particles COUNT       Bounce COUNT particles around the scene, moved on the GPU
+    --secondary             Record the geometry into a secondary command buffer executed by
+                            the frame's, unless the vertices or particles are animated
+    --record-threads COUNT  Same, with the instances split among COUNT threads each recording
+                            a secondary command buffer, compare the record_ms of --profile
+    --blur RADIUS           Box blur the scene over RADIUS pixels, up to 16 [default: 0]
+    --msaa SAMPLES          Anti-alias the edges with 1, 2, 4, 8 or 16 samples per pixel,
+                            lowered to what the device supports [default: 4]
+    --aniso SAMPLES         Filter the textures anisotropically over up to SAMPLES samples,
+                            as many as the device allows
+    --clear R,G,B           sRGB clear color, channels between 0 and 1 [default: 0,0,1]
+    --device TYPE           Prefer a discrete or an integrated GPU [default: discrete]
+    --gpu INDEX             Use the device at INDEX among the ones Vulkan lists, from 0
+    --choose-gpu            Without --gpu, list the devices able to render and ask which one
+                            to use when there are several
+
+Window:
+    --width WIDTH           Width in logical pixels [default: 1024]
+    --height HEIGHT         Height in logical pixels [default: 768]
+    --title TITLE           Title [default: vulkano-training]
+    --monitor INDEX         Start fullscreen on the monitor at INDEX among the ones listed,
+                            from 0, drawing at its exact resolution which lets the
+                            compositor skip a copy and a frame of latency. Alt+Return
+                            goes back and forth to it.
+    --vsync                 Present in sync with the display, whatever the fifo, mailbox or
+                            immediate present mode $VULKANO_PRESENT_MODE asks for
+    --colorspace SPACE      Encode the presented colors as srgb, or store them as-is with
+                            linear, which makes them look too dark [default: srgb]
+    --images COUNT          Ask for COUNT swapchain images, within what the surface allows
+    --max-fps FPS           Cap the frame rate [default: 60 without --vsync, whatever the
+                            display's refresh rate, which can't be queried]
+    --uncapped              Draw as fast as possible without --vsync
+    --reactive              Only redraw on input, resizes and refreshes, which saves power
+                            but makes the animations jump
+    --profile PATH          Write the CPU, acquire, recording and GPU times of every frame
+                            into the CSV file at PATH, then print the average recording
+                            time, to compare it with and without --record-threads
+
+    --help                  Print this help
+
+Controls: WASD and the mouse move the camera, Tab releases the cursor, F toggles
+the wireframe, C culls the back faces, those wound clockwise such as the
+mirrored left quad's, ] and [ widen and narrow the lines, P cycles between
+drawing triangles, a line strip and points, M masks all but the center of the
+scene, R reloads the texture in the background, T spins the geometry on the CPU
+or the GPU, the CPU time of either shows over the scene, Alt+Return fullscreen,
+F12 saves a screenshot and Escape quits.";
+
+/// Window size used without `--width` or `--height`, in logical pixels
+const DEFAULT_WINDOW_WIDTH: u32 = 1024;
+const DEFAULT_WINDOW_HEIGHT: u32 = 768;
+
+/// Window title used without `--title`, followed by the frame rate
+const DEFAULT_TITLE: &str = "vulkano-training";
+
+/// Widest blur `--blur` accepts, every pixel averages (2 * radius + 1)^2 of
+/// them
+const MAX_BLUR_RADIUS: u32 = 16;
+
+/// Radius of the `--blur-image` blur without `--blur`, in pixels
+const DEFAULT_IMAGE_BLUR_RADIUS: u32 = 4;
+
+/// Most samples per pixel `--msaa` accepts, the most any device supports
+const MAX_MSAA_SAMPLES: u32 = 16;
+
+/// Clear color used without `--clear`, an sRGB encoded blue
+const DEFAULT_CLEAR_COLOR: [f32; 3] = [0.0, 0.0, 1.0];
+
+/// Asks for a present mode when `--vsync` isn't given
+const PRESENT_MODE_VAR: &str = "VULKANO_PRESENT_MODE";
+
+/// How the colors written to the swapchain images are encoded
+#[derive(Clone, Copy)]
+pub enum SwapchainEncoding {
+    /// The colors are stored as-is
+    Linear,
+    /// The hardware applies the sRGB curve when storing them
+    Srgb,
+}
+
+impl SwapchainEncoding {
+    /// As given to `--colorspace`
+    pub fn name(self) -> &'static str {
+        match self {
+            SwapchainEncoding::Linear => "linear",
+            SwapchainEncoding::Srgb => "srgb",
+        }
+    }
+}
+
+/// What the program does once started
+#[derive(Clone)]
+pub enum Mode {
+    /// Draws the scene in a window until it's closed
+    Window,
+    /// Renders the Mandelbrot set into an image, checking some of its pixels
+    /// against the CPU when `verify` is set
+    Mandelbrot { verify: bool },
+    /// Renders `frame_count` frames of the scene offscreen
+    Headless { frame_count: u32 },
+    /// Blurs the image at `path` over `radius` pixels
+    BlurImage { path: String, radius: u32 },
+    /// Draws the scene in two windows until both are closed
+    TwoWindows,
+    /// Prints what the window's surface supports
+    Info,
+    /// Fills a window with a compute shader, without any render pass
+    Gradient,
+    /// Lights a cube in a window with deferred shading, in two subpasses
+    Deferred,
+}
+
+/// Which kind of GPU is picked when there are several
+#[derive(Clone, Copy)]
+pub enum DevicePreference {
+    /// Usually the fastest
+    Discrete,
+    /// Usually the one using the least power
+    Integrated,
+}
+
+/// Every setting the program can be started with, read once from the
+/// command line
+#[derive(Clone)]
+pub struct Config {
+    pub mode: Mode,
+    pub geometry: Geometry,
+    pub instance_count: u32,
+    /// Tints the instances at random rather than along a gradient
+    pub rainbow: bool,
+    /// sRGB encoded
+    pub clear_color: [f32; 3],
+    pub show_normals: bool,
+    /// Draws debug lines along the world axes, window mode only
+    pub show_axes: bool,
+    /// Projects the geometry orthographically, in pixels
+    pub ortho: bool,
+    /// Masks the geometry with the stencil buffer
+    pub stencil: bool,
+    /// How the depth of the geometry is tested, reversing the depth range for
+    /// `Greater`
+    pub depth_compare: Compare,
+    /// Paths of the six faces of the cubemap surrounding the scene
+    pub skybox: Option<Vec<String>>,
+    /// Prints the luminance histogram of the frames
+    pub histogram: bool,
+    /// Records the geometry into a secondary command buffer
+    pub secondary: bool,
+    /// Number of threads recording the instances, each into a secondary
+    /// command buffer, `None` to record them on the thread drawing
+    pub record_threads: Option<u32>,
+    /// Number of objects the geometry is drawn as, each with a draw of its
+    /// own, `None` for a single one
+    pub object_count: Option<u32>,
+    /// Number of particles bouncing around the scene, `None` without any
+    pub particle_count: Option<u32>,
+    /// Animates the vertices on the CPU, unless `gpu_wave` is set too
+    pub wave: bool,
+    pub gpu_wave: bool,
+    /// In pixels, 0 without any blur
+    pub blur_radius: u32,
+    /// Samples per pixel the edges are anti-aliased with, `None` for the
+    /// default count
+    pub msaa_samples: Option<u32>,
+    /// Samples the textures are filtered over at most, `None` without any
+    /// anisotropic filtering
+    pub anisotropy: Option<u32>,
+    pub device_preference: DevicePreference,
+    /// Overrides `device_preference`
+    pub gpu_index: Option<usize>,
+    /// Asks which device to use on stdin, unless `gpu_index` is given
+    pub choose_gpu: bool,
+
+    /// In logical pixels
+    pub width: u32,
+    pub height: u32,
+    pub title: String,
+    /// Index of the monitor to start fullscreen on, at its resolution
+    pub monitor: Option<usize>,
+    /// Fifo with `--vsync`, or else what `$VULKANO_PRESENT_MODE` asks for.
+    /// `None` picks the best mode the surface supports.
+    pub present_mode: Option<PresentMode>,
+    pub swapchain_encoding: SwapchainEncoding,
+    /// Swapchain images asked for, `None` leaves it to the present mode
+    pub image_count: Option<u32>,
+    /// `None` caps the frame rate to 60 FPS, unless `uncapped` is set
+    pub max_fps: Option<u32>,
+    pub uncapped: bool,
+    /// Waits for events between frames instead of drawing continuously
+    pub reactive: bool,
+    /// CSV file the timings of every frame are written to
+    pub profile_path: Option<String>,
+}
+
+impl Config {
+    /// Reads the settings from `args`, the program's arguments including its
+    /// name, using the defaults for the ones left out. Returns `None` when
+    /// `--help` is asked for.
+    pub fn from_args(args: &[String]) -> Result<Option<Self>, AppError> {
+        let has_flag = |flag: &str| args.iter().any(|arg| arg == flag);
+
+        if has_flag("--help") || has_flag("-h") {
+            return Ok(None);
+        }
+
+        let swapchain_encoding =
+            match parse_text(args, "--colorspace", "linear or srgb")?.as_deref() {
+                None | Some("srgb") => SwapchainEncoding::Srgb,
+                Some("linear") => SwapchainEncoding::Linear,
+                Some(_) => return Err(AppError::InvalidArgument(
+                    "--colorspace expects linear or srgb".into()
+                )),
+            };
+
+        let blur_radius = parse_count(args, "--blur")?;
+        if blur_radius.is_some_and(|radius| radius > MAX_BLUR_RADIUS) {
+            return Err(AppError::InvalidArgument(
+                format!("--blur expects a radius of at most {}", MAX_BLUR_RADIUS)
+            ));
+        }
+
+        let msaa_samples = parse_count(args, "--msaa")?;
+        if msaa_samples.is_some_and(|samples| !samples.is_power_of_two() || samples > MAX_MSAA_SAMPLES) {
+            return Err(AppError::InvalidArgument("--msaa expects 1, 2, 4, 8 or 16".into()));
+        }
+
+        let mode =
+            if has_flag("--mandelbrot") {
+                Mode::Mandelbrot { verify: has_flag("--verify") }
+            } else if let Some(frame_count) = parse_count(args, "--headless")? {
+                Mode::Headless { frame_count }
+            } else if let Some(path) = parse_text(args, "--blur-image", "a path")? {
+                Mode::BlurImage { path, radius: blur_radius.unwrap_or(DEFAULT_IMAGE_BLUR_RADIUS) }
+            } else if has_flag("--two-windows") {
+                Mode::TwoWindows
+            } else if has_flag("--info") {
+                Mode::Info
+            } else if has_flag("--gradient") {
+                Mode::Gradient
+            } else if has_flag("--deferred") {
+                Mode::Deferred
+            } else {
+                Mode::Window
+            };
+
+        // Only the single window and headless modes read the histogram back
+        if has_flag("--histogram") && matches!(mode, Mode::TwoWindows) {
+            return Err(AppError::InvalidArgument("--histogram can't be used with --two-windows".into()));
+        }
+
+        let geometry =
+            if let Some(path) = parse_text(args, "--obj", "a path")? {
+                Geometry::Mesh { path }
+            } else if let Some(path) = parse_text(args, "--gltf", "a path")? {
+                Geometry::Gltf { path }
+            } else if has_flag("--translucent") {
+                Geometry::Translucent
+            } else if has_flag("--overlapping") {
+                Geometry::Overlapping
+            } else if let Some(vertex_count) = parse_count(args, "--vertices")? {
+                Geometry::Grid { vertex_count }
+            } else {
+                Geometry::Quad
+            };
+
+        let depth_compare =
+            match parse_text(args, "--depth-func", "less, lequal, greater or always")?.as_deref() {
+                None | Some("less") => Compare::Less,
+                Some("lequal") => Compare::LessOrEqual,
+                Some("greater") => Compare::Greater,
+                Some("always") => Compare::Always,
+                Some(_) => return Err(AppError::InvalidArgument(
+                    "--depth-func expects less, lequal, greater or always".into()
+                )),
+            };
+
+        let device_preference =
+            match parse_text(args, "--device", "discrete or integrated")?.as_deref() {
+                None | Some("discrete") => DevicePreference::Discrete,
+                Some("integrated") => DevicePreference::Integrated,
+                Some(_) => return Err(AppError::InvalidArgument(
+                    "--device expects discrete or integrated".into()
+                )),
+            };
+
+        Ok(Some(Config {
+            mode,
+            geometry,
+            instance_count: parse_count(args, "--instances")?.unwrap_or(1),
+            rainbow: has_flag("--rainbow"),
+            clear_color: parse_color(args, "--clear")?.unwrap_or(DEFAULT_CLEAR_COLOR),
+            show_normals: has_flag("--show-normals"),
+            show_axes: has_flag("--show-axes"),
+            ortho: has_flag("--ortho"),
+            stencil: has_flag("--stencil"),
+            depth_compare,
+            skybox: parse_text(args, "--skybox", "six comma separated paths")?
+                .map(|faces| faces.split(',').map(str::to_string).collect()),
+            histogram: has_flag("--histogram"),
+            secondary: has_flag("--secondary"),
+            record_threads: parse_count(args, "--record-threads")?,
+            object_count: parse_count(args, "--objects")?,
+            particle_count: parse_count(args, "--particles")?,
+            wave: has_flag("--wave"),
+            gpu_wave: has_flag("--gpu-wave"),
+            blur_radius: blur_radius.unwrap_or(0),
+            msaa_samples,
+            anisotropy: parse_count(args, "--aniso")?,
+            device_preference,
+            gpu_index: parse_index(args, "--gpu")?,
+            choose_gpu: has_flag("--choose-gpu"),
+
+            width: parse_count(args, "--width")?.unwrap_or(DEFAULT_WINDOW_WIDTH),
+            height: parse_count(args, "--height")?.unwrap_or(DEFAULT_WINDOW_HEIGHT),
+            title: parse_text(args, "--title", "a title")?.unwrap_or_else(|| DEFAULT_TITLE.to_string()),
+            monitor: parse_index(args, "--monitor")?,
+            present_mode: if has_flag("--vsync") { Some(PresentMode::Fifo) } else { present_mode_from_env() },
+            swapchain_encoding,
+            image_count: parse_count(args, "--images")?,
+            max_fps: parse_count(args, "--max-fps")?,
+            uncapped: has_flag("--uncapped"),
+            reactive: has_flag("--reactive"),
+            profile_path: parse_text(args, "--profile", "a path")?,
+        }))
+    }
+}
+
+/// Reads the present mode `$VULKANO_PRESENT_MODE` asks for, handier than a
+/// flag for scripts. An unknown mode is ignored, with a warning.
+fn present_mode_from_env() -> Option<PresentMode> {
+    let value = env::var(PRESENT_MODE_VAR).ok()?;
+
+    match value.as_str() {
+        "fifo" => Some(PresentMode::Fifo),
+        "mailbox" => Some(PresentMode::Mailbox),
+        "immediate" => Some(PresentMode::Immediate),
+        _ => {
+            println!(
+                "Warning: ignoring {}={}, expected fifo, mailbox or immediate", PRESENT_MODE_VAR, value
+            );
+            None
+        },
+    }
+}
+
+/// Reads the `N` of `<flag> N`, such as `--vertices N` giving the approximate
+/// number of vertices of the grid drawn instead of the quad, `--instances N`
+/// giving how many copies of it are drawn, `--headless N` giving the number
+/// of frames to render offscreen or `--max-fps N` capping the frame rate
+fn parse_count(args: &[String], flag: &str) -> Result<Option<u32>, AppError> {
+    let position = match args.iter().position(|arg| arg == flag) {
+        Some(position) => position,
+        None => return Ok(None),
+    };
+
+    args.get(position + 1)
+        .and_then(|count| count.parse::<u32>().ok())
+        .filter(|&count| count > 0)
+        .map(Some)
+        .ok_or_else(|| AppError::InvalidArgument(format!("{} expects a positive number", flag)))
+}
+
+/// Reads the `N` of `<flag> N` where N counts from 0, such as `--gpu N`
+/// giving the index of the device to use
+fn parse_index(args: &[String], flag: &str) -> Result<Option<usize>, AppError> {
+    let position = match args.iter().position(|arg| arg == flag) {
+        Some(position) => position,
+        None => return Ok(None),
+    };
+
+    args.get(position + 1)
+        .and_then(|index| index.parse::<usize>().ok())
+        .map(Some)
+        .ok_or_else(|| AppError::InvalidArgument(format!("{} expects a number", flag)))
+}
+
+/// Reads the `TEXT` of `<flag> TEXT`, such as `--obj PATH` or `--title TITLE`,
+/// naming what's expected as `what` when it's missing
+fn parse_text(args: &[String], flag: &str, what: &str) -> Result<Option<String>, AppError> {
+    let position = match args.iter().position(|arg| arg == flag) {
+        Some(position) => position,
+        None => return Ok(None),
+    };
+
+    args.get(position + 1)
+        .cloned()
+        .map(Some)
+        .ok_or_else(|| AppError::InvalidArgument(format!("{} expects {}", flag, what)))
+}
+
+/// Reads the `R,G,B` of `<flag> R,G,B`, three comma separated numbers between
+/// 0 and 1
+fn parse_color(args: &[String], flag: &str) -> Result<Option<[f32; 3]>, AppError> {
+    let position = match args.iter().position(|arg| arg == flag) {
+        Some(position) => position,
+        None => return Ok(None),
+    };
+
+    let error = || AppError::InvalidArgument(format!("{} expects R,G,B between 0 and 1", flag));
+
+    let channels =
+        args.get(position + 1)
+            .ok_or_else(error)?
+            .split(',')
+            .map(|channel| channel.trim().parse::<f32>().ok().filter(|c| (0.0 ..= 1.0).contains(c)))
+            .collect::<Option<Vec<f32>>>()
+            .ok_or_else(error)?;
+
+    match channels[..] {
+        [r, g, b] => Ok(Some([r, g, b])),
+        _ => Err(error()),
+    }
+}
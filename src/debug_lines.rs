@@ -0,0 +1,130 @@
+// Build-in modules
+use std::sync::Arc;
+
+// External modules
+use vulkano::buffer::BufferUsage;
+use vulkano::buffer::CpuAccessibleBuffer;
+use vulkano::command_buffer::AutoCommandBufferBuilder;
+use vulkano::command_buffer::DynamicState;
+use vulkano::device::Device;
+use vulkano::framebuffer::RenderPassAbstract;
+use vulkano::framebuffer::Subpass;
+use vulkano::pipeline::GraphicsPipeline;
+use vulkano::pipeline::GraphicsPipelineAbstract;
+use vulkano::pipeline::vertex::SingleBufferDefinition;
+
+// Internal modules
+use crate::error::AppError;
+
+/// One end of a line
+#[derive(Default, Copy, Clone)]
+struct LineVertex {
+    /// In world space
+    position: [f32; 3],
+    /// Linear
+    color: [f32; 3],
+}
+vulkano::impl_vertex!(LineVertex, position, color);
+
+mod vs {
+    vulkano_shaders::shader!{
+        ty: "vertex",
+        path: "src/shaders/debug_lines.vert"
+    }
+}
+
+mod fs {
+    vulkano_shaders::shader!{
+        ty: "fragment",
+        path: "src/shaders/debug_lines.frag"
+    }
+}
+
+/// Collects line segments during a frame, to draw them over the scene. Meant
+/// for visualizing what's otherwise invisible, such as bounds or directions.
+pub struct DebugLines {
+    device: Arc<Device>,
+    pipeline: Arc<dyn GraphicsPipelineAbstract + Send + Sync>,
+    /// Two per line
+    vertices: Vec<LineVertex>,
+}
+
+impl DebugLines {
+    /// Prepares drawing the lines in the subpass `subpass` of `render_pass`
+    pub fn new(
+        device: Arc<Device>,
+        render_pass: &Arc<dyn RenderPassAbstract + Send + Sync>,
+        subpass: u32
+    ) -> Result<Self, AppError> {
+        let vs = vs::Shader::load(device.clone())?;
+        let fs = fs::Shader::load(device.clone())?;
+
+        // Without any depth test, they show through the geometry
+        let pipeline =
+            Arc::new(
+                GraphicsPipeline::start()
+                    .vertex_input(SingleBufferDefinition::<LineVertex>::new())
+                    .vertex_shader(vs.main_entry_point(), ())
+                    .line_list()
+                    .line_width_dynamic()
+                    .viewports_scissors_dynamic(1)
+                    .fragment_shader(fs.main_entry_point(), ())
+                    .render_pass(
+                        Subpass::from(render_pass.clone(), subpass)
+                            .ok_or(AppError::MissingSubpass(subpass))?
+                    )
+                    .build(device.clone())?
+            );
+
+        Ok(DebugLines { device, pipeline, vertices: Vec::new() })
+    }
+
+    /// Draws a line from `a` to `b` in the linear `color`
+    pub fn add_line(&mut self, a: [f32; 3], b: [f32; 3], color: [f32; 3]) {
+        self.vertices.push(LineVertex { position: a, color });
+        self.vertices.push(LineVertex { position: b, color });
+    }
+
+    /// Draws the edges of the axis aligned box going from `min` to `max`
+    pub fn add_aabb(&mut self, min: [f32; 3], max: [f32; 3], color: [f32; 3]) {
+        let corner = |x: bool, y: bool, z: bool|
+            [if x { max[0] } else { min[0] }, if y { max[1] } else { min[1] }, if z { max[2] } else { min[2] }];
+
+        // Each edge joins two corners differing along a single axis
+        for &(a, b) in &[(false, false), (false, true), (true, false), (true, true)] {
+            self.add_line(corner(false, a, b), corner(true, a, b), color);
+            self.add_line(corner(a, false, b), corner(a, true, b), color);
+            self.add_line(corner(a, b, false), corner(a, b, true), color);
+        }
+    }
+
+    /// Forgets every line added so far, which is done once per frame
+    pub fn clear(&mut self) {
+        self.vertices.clear();
+    }
+
+    /// Records the draw of the lines added so far, seen as `view_projection`
+    /// transforms them, within the subpass they were prepared for. Their width
+    /// is the one `dynamic_state` gives.
+    pub fn draw(
+        &self,
+        builder: AutoCommandBufferBuilder,
+        dynamic_state: &DynamicState,
+        view_projection: [[f32; 4]; 4]
+    ) -> Result<AutoCommandBufferBuilder, AppError> {
+        // An empty buffer can't be created
+        if self.vertices.is_empty() {
+            return Ok(builder);
+        }
+
+        let vertex_buffer =
+            CpuAccessibleBuffer::from_iter(
+                self.device.clone(), BufferUsage::vertex_buffer(), self.vertices.iter().cloned()
+            )?;
+
+        Ok(builder.draw(
+            self.pipeline.clone(), dynamic_state, vec![vertex_buffer], (),
+            vs::ty::PushConstants { view_projection }
+        )?)
+    }
+}
@@ -0,0 +1,432 @@
+// Build-in modules
+use std::sync::Arc;
+use std::time::Instant;
+
+// External modules
+use cgmath::{InnerSpace, Matrix4, Point3, Rad, Vector3};
+use vulkano::buffer::BufferUsage;
+use vulkano::buffer::CpuAccessibleBuffer;
+use vulkano::command_buffer::AutoCommandBufferBuilder;
+use vulkano::command_buffer::DynamicState;
+use vulkano::descriptor::descriptor_set::PersistentDescriptorSet;
+use vulkano::descriptor::DescriptorSet;
+use vulkano::device::Device;
+use vulkano::device::DeviceExtensions;
+use vulkano::format::ClearValue;
+use vulkano::format::Format;
+use vulkano::framebuffer::{Framebuffer, FramebufferAbstract, RenderPassAbstract, Subpass};
+use vulkano::image::AttachmentImage;
+use vulkano::image::swapchain::SwapchainImage;
+use vulkano::pipeline::GraphicsPipeline;
+use vulkano::pipeline::GraphicsPipelineAbstract;
+use vulkano::pipeline::vertex::BufferlessVertices;
+use vulkano::pipeline::viewport::Viewport;
+use vulkano::swapchain::AcquireError;
+use vulkano::swapchain::SwapchainCreationError;
+use vulkano::swapchain;
+use vulkano::sync::FlushError;
+use vulkano::sync::GpuFuture;
+use vulkano::sync;
+use vulkano_win::VkSurfaceBuild;
+use winit::ElementState;
+use winit::EventsLoop;
+use winit::KeyboardInput;
+use winit::VirtualKeyCode;
+use winit::Window;
+use winit::WindowBuilder;
+
+// Internal modules
+use crate::config::Config;
+use crate::error::AppError;
+use crate::renderer::{self, SwapchainUsage, VulkanContext};
+use crate::scene::{self, BufferlessPipeline};
+
+/// Formats of the G-buffer: colors as they are, and normals whose components
+/// go from -1 to 1, which a floating point format keeps signed and precise
+const ALBEDO_FORMAT: Format = Format::R8G8B8A8Unorm;
+const NORMAL_FORMAT: Format = Format::R16G16B16A16Sfloat;
+/// Supported as a depth attachment by every device
+const DEPTH_FORMAT: Format = Format::D16Unorm;
+
+/// Albedo of the pixels no geometry covers, left unlit
+const BACKGROUND_COLOR: [f32; 4] = [0.02, 0.02, 0.05, 1.0];
+
+/// Direction the light travels in, towards the bottom right and away from the
+/// camera
+const LIGHT_DIR: [f32; 3] = [0.5, -0.7, -0.5];
+
+/// Where the camera looks at the cube from
+const CAMERA_POSITION: [f32; 3] = [0.0, 0.0, 2.5];
+
+/// A side of the cube, spanned by two axes whose cross product is its normal
+struct Face {
+    normal: [f32; 3],
+    u: [f32; 3],
+    v: [f32; 3],
+    color: [f32; 3],
+}
+
+const CUBE_FACES: [Face; 6] = [
+    Face { normal: [ 1.0,  0.0,  0.0], u: [0.0, 1.0, 0.0], v: [0.0, 0.0, 1.0], color: [0.9, 0.2, 0.2] },
+    Face { normal: [-1.0,  0.0,  0.0], u: [0.0, 0.0, 1.0], v: [0.0, 1.0, 0.0], color: [0.2, 0.9, 0.9] },
+    Face { normal: [ 0.0,  1.0,  0.0], u: [0.0, 0.0, 1.0], v: [1.0, 0.0, 0.0], color: [0.2, 0.9, 0.2] },
+    Face { normal: [ 0.0, -1.0,  0.0], u: [1.0, 0.0, 0.0], v: [0.0, 0.0, 1.0], color: [0.9, 0.2, 0.9] },
+    Face { normal: [ 0.0,  0.0,  1.0], u: [1.0, 0.0, 0.0], v: [0.0, 1.0, 0.0], color: [0.2, 0.2, 0.9] },
+    Face { normal: [ 0.0,  0.0, -1.0], u: [0.0, 1.0, 0.0], v: [1.0, 0.0, 0.0], color: [0.9, 0.9, 0.2] },
+];
+
+#[derive(Default, Copy, Clone)]
+struct Vertex {
+    position: [f32; 3],
+    normal: [f32; 3],
+    color: [f32; 3],
+}
+vulkano::impl_vertex!(Vertex, position, normal, color);
+
+mod geometry_vs {
+    vulkano_shaders::shader!{
+        ty: "vertex",
+        path: "src/shaders/deferred_geometry.vert"
+    }
+}
+
+mod geometry_fs {
+    vulkano_shaders::shader!{
+        ty: "fragment",
+        path: "src/shaders/deferred_geometry.frag"
+    }
+}
+
+mod lighting_vs {
+    vulkano_shaders::shader!{
+        ty: "vertex",
+        path: "src/shaders/post.vert"
+    }
+}
+
+mod lighting_fs {
+    vulkano_shaders::shader!{
+        ty: "fragment",
+        path: "src/shaders/deferred_lighting.frag"
+    }
+}
+
+/// What's sized after the swapchain images, created again along with them
+struct Targets {
+    /// One per swapchain image, all sharing the same G-buffer
+    framebuffers: Vec<Arc<dyn FramebufferAbstract + Send + Sync>>,
+    /// Gives the lighting subpass the G-buffer as input attachments
+    lighting_set: Arc<dyn DescriptorSet + Send + Sync>,
+    dynamic_state: DynamicState,
+}
+
+/// Draws a spinning cube in a window with deferred shading, within a single
+/// render pass of two subpasses. The first rasterizes the cube into the
+/// G-buffer, its albedo and normals, without any lighting. The second runs
+/// the lighting once per pixel, over a triangle covering the window, reading
+/// the G-buffer back as input attachments.
+///
+/// The render pass has four attachments, in this order:
+///
+/// - `albedo`, `ALBEDO_FORMAT`: written by the first subpass, read by the
+///   second. Cleared to `BACKGROUND_COLOR`, never stored.
+/// - `normal`, `NORMAL_FORMAT`: same, holding world space normals. Cleared to
+///   zero, which marks the pixels without any geometry.
+/// - `depth`, `DEPTH_FORMAT`: the depth test of the first subpass. Cleared,
+///   never stored.
+/// - `color`, the swapchain's format: the lit result the second subpass
+///   writes, stored to be presented.
+///
+/// The G-buffer never leaves the render pass, so its images are transient:
+/// tiled GPUs can keep them in on-chip memory without ever writing them out.
+/// Input attachments only give access to the fragment's own pixel, which is
+/// all lighting needs, unlike the blur of the scene's post-processing.
+pub fn run(config: &Config) -> Result<(), AppError> {
+    let context = VulkanContext::new(vulkano_win::required_extensions())?;
+    let extensions = DeviceExtensions { khr_swapchain: true, .. DeviceExtensions::none() };
+    let (device, queue, _) = context.open_device(config, &extensions)?;
+    let physical_device = device.physical_device();
+
+    let mut events_loop = EventsLoop::new();
+    let surface =
+        WindowBuilder::new()
+            .with_dimensions((config.width, config.height).into())
+            .with_title(config.title.clone())
+            .build_vk_surface(&events_loop, context.instance.clone())?;
+
+    let capabilities = surface.capabilities(physical_device)?;
+    let swapchain_format = renderer::choose_swapchain_format(&capabilities, config.swapchain_encoding);
+    let (mut swapchain, images) =
+        renderer::create_swapchain(
+            &device, &queue, surface.clone(), swapchain_format, config.present_mode, config.image_count,
+            SwapchainUsage::draw()
+        )?;
+
+    let render_pass =
+        Arc::new(
+            vulkano::ordered_passes_renderpass!(
+                device.clone(),
+                attachments: {
+                    albedo: {
+                        load: Clear,
+                        store: DontCare,
+                        format: ALBEDO_FORMAT,
+                        samples: 1,
+                    },
+                    normal: {
+                        load: Clear,
+                        store: DontCare,
+                        format: NORMAL_FORMAT,
+                        samples: 1,
+                    },
+                    depth: {
+                        load: Clear,
+                        store: DontCare,
+                        format: DEPTH_FORMAT,
+                        samples: 1,
+                    },
+                    // Every pixel is written by the lighting, nothing to clear
+                    color: {
+                        load: DontCare,
+                        store: Store,
+                        format: swapchain_format,
+                        samples: 1,
+                    }
+                },
+                passes: [
+                    // The G-buffer, its outputs in the order of `color`
+                    {
+                        color: [albedo, normal],
+                        depth_stencil: {depth},
+                        input: []
+                    },
+                    // The lighting, its inputs bound in the order of `input`.
+                    // vulkano adds the dependency between both subpasses: the
+                    // color writes of the first before the reads of the second.
+                    {
+                        color: [color],
+                        depth_stencil: {},
+                        input: [albedo, normal]
+                    }
+                ]
+            )?
+        ) as Arc<dyn RenderPassAbstract + Send + Sync>;
+
+    let geometry_vs = geometry_vs::Shader::load(device.clone())?;
+    let geometry_fs = geometry_fs::Shader::load(device.clone())?;
+    let geometry_pipeline =
+        Arc::new(
+            GraphicsPipeline::start()
+                .vertex_input_single_buffer::<Vertex>()
+                .vertex_shader(geometry_vs.main_entry_point(), ())
+                .triangle_list()
+                .viewports_dynamic_scissors_irrelevant(1)
+                .fragment_shader(geometry_fs.main_entry_point(), ())
+                .depth_stencil_simple_depth()
+                .render_pass(Subpass::from(render_pass.clone(), 0).ok_or(AppError::MissingSubpass(0))?)
+                .build(device.clone())?
+        ) as Arc<dyn GraphicsPipelineAbstract + Send + Sync>;
+
+    let lighting_vs = lighting_vs::Shader::load(device.clone())?;
+    let lighting_fs = lighting_fs::Shader::load(device.clone())?;
+    let lighting_pipeline: Arc<BufferlessPipeline> =
+        Arc::new(
+            GraphicsPipeline::start()
+                .vertex_shader(lighting_vs.main_entry_point(), ())
+                .viewports_dynamic_scissors_irrelevant(1)
+                .fragment_shader(lighting_fs.main_entry_point(), ())
+                .render_pass(Subpass::from(render_pass.clone(), 1).ok_or(AppError::MissingSubpass(1))?)
+                .build(device.clone())?
+        );
+
+    let (vertices, indices) = make_cube();
+    let vertex_buffer =
+        CpuAccessibleBuffer::from_iter(device.clone(), BufferUsage::vertex_buffer(), vertices.into_iter())?;
+    let index_buffer =
+        CpuAccessibleBuffer::from_iter(device.clone(), BufferUsage::index_buffer(), indices.into_iter())?;
+
+    let mut targets = create_targets(&device, &render_pass, &lighting_pipeline, &images)?;
+
+    let light_dir = Vector3::from(LIGHT_DIR).normalize();
+    let start_time = Instant::now();
+    let mut previous_frame_end = Box::new(sync::now(device.clone())) as Box<dyn GpuFuture>;
+    let mut swapchain_outdated = false;
+
+    loop {
+        previous_frame_end.cleanup_finished();
+
+        // The G-buffer follows the swapchain's size, its pixels map one to one
+        if swapchain_outdated {
+            let capabilities = surface.capabilities(physical_device)?;
+            let dimensions = renderer::get_dimensions(&surface, &capabilities);
+
+            match swapchain.recreate_with_dimension(dimensions) {
+                Ok((new_swapchain, new_images)) => {
+                    swapchain = new_swapchain;
+                    targets = create_targets(&device, &render_pass, &lighting_pipeline, &new_images)?;
+                    swapchain_outdated = false;
+                },
+                // Happens while the user is resizing the window, try again next frame
+                Err(SwapchainCreationError::UnsupportedDimensions) => (),
+                Err(err) => return Err(err.into()),
+            }
+        }
+
+        if !swapchain_outdated {
+            match swapchain::acquire_next_image(swapchain.clone(), None) {
+                Ok((image_num, acquire_future)) => {
+                    let [width, height] = swapchain.dimensions();
+                    let time = start_time.elapsed().as_secs_f32();
+
+                    let view =
+                        Matrix4::look_at(
+                            Point3::from(CAMERA_POSITION), Point3::new(0.0, 0.0, 0.0),
+                            Vector3::new(0.0, 1.0, 0.0)
+                        );
+                    let projection = scene::perspective_projection(width as f32, height as f32);
+                    let view_projection = scene::vulkan_correction() * projection * view;
+                    let model = Matrix4::from_axis_angle(Vector3::new(1.0, 1.0, 0.0).normalize(), Rad(time));
+
+                    let geometry_push_constants =
+                        geometry_vs::ty::PushConstants {
+                            view_projection: view_projection.into(),
+                            model: model.into(),
+                        };
+                    let lighting_push_constants =
+                        lighting_fs::ty::PushConstants { light_dir: light_dir.extend(0.0).into() };
+
+                    // In the order of the attachments, the color one is never
+                    // cleared
+                    let clear_values =
+                        vec![
+                            BACKGROUND_COLOR.into(),
+                            [0.0, 0.0, 0.0, 0.0].into(),
+                            ClearValue::Depth(1.0),
+                            ClearValue::None,
+                        ];
+
+                    let command_buffer =
+                        AutoCommandBufferBuilder::primary_one_time_submit(device.clone(), queue.family())?
+                            .begin_render_pass(targets.framebuffers[image_num].clone(), false, clear_values)?
+                            .draw_indexed(
+                                geometry_pipeline.clone(), &targets.dynamic_state,
+                                vec![vertex_buffer.clone()], index_buffer.clone(), (), geometry_push_constants
+                            )?
+                            .next_subpass(false)?
+                            .draw(
+                                lighting_pipeline.clone(), &targets.dynamic_state,
+                                BufferlessVertices { vertices: 3, instances: 1 },
+                                targets.lighting_set.clone(), lighting_push_constants
+                            )?
+                            .end_render_pass()?
+                            .build()?;
+
+                    let future =
+                        previous_frame_end
+                            .join(acquire_future)
+                            .then_execute(queue.clone(), command_buffer)?
+                            .then_swapchain_present(queue.clone(), swapchain.clone(), image_num)
+                            .then_signal_fence_and_flush();
+
+                    previous_frame_end = match future {
+                        Ok(future) => Box::new(future),
+                        Err(FlushError::OutOfDate) => {
+                            swapchain_outdated = true;
+                            Box::new(sync::now(device.clone()))
+                        },
+                        Err(err) => return Err(err.into()),
+                    };
+                },
+                Err(AcquireError::OutOfDate) => swapchain_outdated = true,
+                Err(err) => return Err(err.into()),
+            }
+        }
+
+        let mut done = false;
+        events_loop.poll_events(|event| {
+            if let winit::Event::WindowEvent { event, .. } = event {
+                match event {
+                    winit::WindowEvent::CloseRequested
+                    | winit::WindowEvent::KeyboardInput {
+                        input: KeyboardInput {
+                            state: ElementState::Pressed, virtual_keycode: Some(VirtualKeyCode::Escape), ..
+                        },
+                        ..
+                    } => done = true,
+                    winit::WindowEvent::Resized(_) | winit::WindowEvent::HiDpiFactorChanged(_) =>
+                        swapchain_outdated = true,
+                    _ => (),
+                }
+            }
+        });
+        if done {
+            return Ok(());
+        }
+    }
+}
+
+/// Creates the G-buffer at the size of `images`, along with the framebuffers
+/// drawing into each of them and the set `lighting_pipeline` reads it through
+fn create_targets(
+    device: &Arc<Device>,
+    render_pass: &Arc<dyn RenderPassAbstract + Send + Sync>,
+    lighting_pipeline: &Arc<BufferlessPipeline>,
+    images: &[Arc<SwapchainImage<Window>>]
+) -> Result<Targets, AppError> {
+    let dimensions = images[0].dimensions();
+
+    let albedo = AttachmentImage::transient_input_attachment(device.clone(), dimensions, ALBEDO_FORMAT)?;
+    let normal = AttachmentImage::transient_input_attachment(device.clone(), dimensions, NORMAL_FORMAT)?;
+    let depth = AttachmentImage::transient(device.clone(), dimensions, DEPTH_FORMAT)?;
+
+    // Frames in flight share the G-buffer safely: vulkano orders the render
+    // passes using it one after the other
+    let framebuffers =
+        images.iter().map(|image| {
+            Ok(Arc::new(
+                Framebuffer::start(render_pass.clone())
+                    .add(albedo.clone())?
+                    .add(normal.clone())?
+                    .add(depth.clone())?
+                    .add(image.clone())?
+                    .build()?
+            ) as Arc<dyn FramebufferAbstract + Send + Sync>)
+        }).collect::<Result<Vec<_>, AppError>>()?;
+
+    let lighting_set =
+        Arc::new(
+            PersistentDescriptorSet::start(lighting_pipeline.clone(), 0)
+                .add_image(albedo)?
+                .add_image(normal)?
+                .build()?
+        );
+
+    let dynamic_state =
+        DynamicState {
+            viewports: Some(vec![Viewport {
+                origin: [0.0, 0.0],
+                dimensions: [dimensions[0] as f32, dimensions[1] as f32],
+                depth_range: 0.0 .. 1.0,
+            }]),
+            .. DynamicState::none()
+        };
+
+    Ok(Targets { framebuffers, lighting_set, dynamic_state })
+}
+
+/// A cube one unit wide around the origin, each face flat and of its own
+/// color, wound counter-clockwise seen from outside
+fn make_cube() -> (Vec<Vertex>, Vec<u16>) {
+    let mut vertices = Vec::with_capacity(4 * CUBE_FACES.len());
+    let mut indices = Vec::with_capacity(6 * CUBE_FACES.len());
+    for Face { normal, u, v, color } in CUBE_FACES.iter() {
+        let first = vertices.len() as u16;
+        for (su, sv) in [(-1.0, -1.0), (1.0, -1.0), (1.0, 1.0), (-1.0, 1.0)].iter() {
+            let position = [0, 1, 2].map(|i| 0.5 * (normal[i] + su * u[i] + sv * v[i]));
+            vertices.push(Vertex { position, normal: *normal, color: *color });
+        }
+        indices.extend([0, 1, 2, 2, 3, 0].iter().map(|i| first + i));
+    }
+
+    (vertices, indices)
+}
@@ -0,0 +1,203 @@
+// Build-in modules
+use std::io;
+
+// External modules
+use rayon::ThreadPoolBuildError;
+use thiserror::Error;
+use vulkano::OomError;
+use vulkano::buffer::BufferCreationError;
+use vulkano::buffer::cpu_access::ReadLockError;
+use vulkano::buffer::cpu_access::WriteLockError;
+use vulkano::command_buffer::AutoCommandBufferBuilderContextError;
+use vulkano::command_buffer::BeginRenderPassError;
+use vulkano::command_buffer::BlitImageError;
+use vulkano::command_buffer::BuildError;
+use vulkano::command_buffer::CommandBufferExecError;
+use vulkano::command_buffer::CopyBufferError;
+use vulkano::command_buffer::CopyBufferImageError;
+use vulkano::command_buffer::DispatchError;
+use vulkano::command_buffer::DrawError;
+use vulkano::command_buffer::DrawIndexedError;
+use vulkano::command_buffer::ExecuteCommandsError;
+use vulkano::command_buffer::FillBufferError;
+use vulkano::descriptor::descriptor_set::PersistentDescriptorSetBuildError;
+use vulkano::descriptor::descriptor_set::PersistentDescriptorSetError;
+use vulkano::device::DeviceCreationError;
+use vulkano::framebuffer::FramebufferCreationError;
+use vulkano::framebuffer::RenderPassCreationError;
+use vulkano::image::ImageCreationError;
+use vulkano::instance::InstanceCreationError;
+use vulkano::instance::LayersListError;
+use vulkano::instance::loader::LoadingError;
+use vulkano::memory::DeviceMemoryAllocError;
+use vulkano::pipeline::ComputePipelineCreationError;
+use vulkano::pipeline::GraphicsPipelineCreationError;
+use vulkano::query::QueryPoolCreationError;
+use vulkano::sampler::SamplerCreationError;
+use vulkano::swapchain::AcquireError;
+use vulkano::swapchain::CapabilitiesError;
+use vulkano::swapchain::SwapchainCreationError;
+use vulkano::sync::FlushError;
+
+// Internal modules
+#[cfg(debug_assertions)]
+use crate::hot_reload::ShaderDiagnostics;
+
+/// Every way the application can fail
+#[derive(Debug, Error)]
+pub enum AppError {
+    #[error("invalid command line argument: {0}")]
+    InvalidArgument(String),
+
+    #[error("could not load the Vulkan library")]
+    Loading(#[from] LoadingError),
+    #[error("could not list the available layers")]
+    LayersList(#[from] LayersListError),
+    #[error("could not create the Vulkan instance")]
+    InstanceCreation(#[from] InstanceCreationError),
+    #[error("no Vulkan capable device found")]
+    NoDevice,
+    #[error("no suitable physical device: {0}")]
+    NoSuitableDevice(String),
+    #[error("the device doesn't support the {0} feature")]
+    MissingFeature(String),
+    #[error("the surface doesn't support {0} swapchain images")]
+    MissingSwapchainUsage(String),
+    #[error("no device at index {index}, only {count} found")]
+    DeviceIndexOutOfRange { index: usize, count: usize },
+    #[error("could not create the logical device")]
+    DeviceCreation(#[from] DeviceCreationError),
+
+    #[error("could not create the window")]
+    WindowCreation(#[from] vulkano_win::CreationError),
+    #[error("could not query the surface capabilities")]
+    Capabilities(#[from] CapabilitiesError),
+    #[error("could not create the swapchain")]
+    SwapchainCreation(#[from] SwapchainCreationError),
+    #[error("could not acquire the next swapchain image")]
+    Acquire(#[from] AcquireError),
+
+    #[error("out of memory")]
+    Oom(#[from] OomError),
+    #[error("no host visible and coherent memory type for the uniforms")]
+    NoHostCoherentMemory,
+    #[error("could not allocate device memory")]
+    DeviceMemoryAlloc(#[from] DeviceMemoryAllocError),
+    #[error("could not create a buffer")]
+    BufferCreation(#[from] BufferCreationError),
+    #[error("could not create an image")]
+    ImageCreation(#[from] ImageCreationError),
+    #[error("could not create the render pass")]
+    RenderPassCreation(#[from] RenderPassCreationError),
+    #[error("the render pass has no subpass {0}")]
+    MissingSubpass(u32),
+    #[error("could not create a framebuffer")]
+    FramebufferCreation(#[from] FramebufferCreationError),
+    #[error("could not create a graphics pipeline")]
+    GraphicsPipelineCreation(#[from] GraphicsPipelineCreationError),
+    #[error("could not create a compute pipeline")]
+    ComputePipelineCreation(#[from] ComputePipelineCreationError),
+    #[error("could not create a sampler")]
+    SamplerCreation(#[from] SamplerCreationError),
+    #[error("could not add a resource to a descriptor set")]
+    DescriptorSet(#[from] PersistentDescriptorSetError),
+    #[error("could not build a descriptor set")]
+    DescriptorSetBuild(#[from] PersistentDescriptorSetBuildError),
+
+    #[error("could not record a command")]
+    CommandContext(#[from] AutoCommandBufferBuilderContextError),
+    #[error("could not begin the render pass")]
+    BeginRenderPass(#[from] BeginRenderPassError),
+    #[error("could not record a draw command")]
+    Draw(#[from] DrawError),
+    #[error("could not record an indexed draw command")]
+    DrawIndexed(#[from] DrawIndexedError),
+    #[error("could not record a dispatch command")]
+    Dispatch(#[from] DispatchError),
+    #[error("could not record a buffer fill")]
+    FillBuffer(#[from] FillBufferError),
+    #[error("could not record a copy between buffers")]
+    CopyBuffer(#[from] CopyBufferError),
+    #[error("could not record a copy between a buffer and an image")]
+    CopyBufferImage(#[from] CopyBufferImageError),
+    #[error("could not record a blit between images")]
+    BlitImage(#[from] BlitImageError),
+    #[error("could not record the execution of a secondary command buffer")]
+    ExecuteCommands(#[from] ExecuteCommandsError),
+    #[error("could not start the command recording threads")]
+    ThreadPoolBuild(#[from] ThreadPoolBuildError),
+    #[error("could not create a query pool")]
+    QueryPoolCreation(#[from] QueryPoolCreationError),
+    #[error("could not build a command buffer")]
+    CommandBufferBuild(#[from] BuildError),
+    #[error("could not execute a command buffer")]
+    CommandBufferExec(#[from] CommandBufferExecError),
+    #[error("could not flush the submitted work")]
+    Flush(#[from] FlushError),
+
+    #[error("could not read back a buffer")]
+    ReadLock(#[from] ReadLockError),
+    #[error("could not write into a buffer")]
+    WriteLock(#[from] WriteLockError),
+    #[error("could not build an image: {0}")]
+    InvalidImageData(&'static str),
+    #[error("{0} pixels rendered by the GPU don't match the CPU")]
+    VerificationFailed(usize),
+    #[cfg(debug_assertions)]
+    #[error("could not watch the shaders: {0}")]
+    Watch(#[from] notify::Error),
+    #[cfg(debug_assertions)]
+    #[error("could not initialize the shader compiler")]
+    ShaderCompilerCreation,
+    #[cfg(debug_assertions)]
+    #[error("could not compile a shader: {0}")]
+    ShaderCompilation(#[from] shaderc::Error),
+    #[cfg(debug_assertions)]
+    #[error("the shader source has errors:\n{0}")]
+    ShaderSource(ShaderDiagnostics),
+    #[error("could not load the OBJ mesh")]
+    ObjLoad(#[from] tobj::LoadError),
+    #[error("could not load the glTF model")]
+    GltfLoad(#[from] gltf::Error),
+    #[error("could not draw the model: {0}")]
+    UnsupportedModel(&'static str),
+    #[error("could not encode or decode an image")]
+    Image(#[from] image::ImageError),
+    #[error("input/output error")]
+    Io(#[from] io::Error),
+}
+
+impl AppError {
+    /// Explains how to fix the errors users most likely hit on their first run,
+    /// which come from their system setup rather than from this program
+    pub fn hint(&self) -> Option<&'static str> {
+        match self {
+            AppError::Loading(_)
+            | AppError::LayersList(LayersListError::LoadingError(_))
+            | AppError::InstanceCreation(InstanceCreationError::LoadingError(_)) => Some(
+                "The Vulkan loader (libvulkan.so.1 or vulkan-1.dll) could not be found. Install \
+                 the Vulkan runtime along with the drivers of your GPU, for example the \
+                 mesa-vulkan-drivers and libvulkan1 packages on Debian based distributions."
+            ),
+            AppError::InstanceCreation(InstanceCreationError::IncompatibleDriver)
+            | AppError::NoDevice => Some(
+                "The Vulkan loader is installed but no driver exposes a usable GPU. Make sure \
+                 the drivers of your GPU are installed and support Vulkan, `vulkaninfo` lists \
+                 the devices the loader can see."
+            ),
+            _ => None,
+        }
+    }
+
+    /// Whether the device can't be used anymore, after a driver reset or when
+    /// its memory ran out, while a new one might work
+    pub fn requires_new_device(&self) -> bool {
+        matches!(
+            self,
+            AppError::Flush(FlushError::DeviceLost)
+            | AppError::Flush(FlushError::OomError(OomError::OutOfDeviceMemory))
+            | AppError::Acquire(AcquireError::DeviceLost)
+            | AppError::Acquire(AcquireError::OomError(OomError::OutOfDeviceMemory))
+        )
+    }
+}
@@ -0,0 +1,256 @@
+// Build-in modules
+use std::collections::VecDeque;
+use std::sync::Arc;
+
+// External modules
+use vulkano::buffer::BufferAccess;
+use vulkano::buffer::BufferUsage;
+use vulkano::buffer::CpuAccessibleBuffer;
+use vulkano::command_buffer::CommandBuffer;
+use vulkano::command_buffer::CommandBufferExecError;
+use vulkano::command_buffer::pool::standard::{StandardCommandPoolAlloc, StandardCommandPoolBuilder};
+use vulkano::command_buffer::sys::{Flags, Kind, UnsafeCommandBuffer, UnsafeCommandBufferBuilder};
+use vulkano::device::{Device, DeviceOwned, Queue};
+use vulkano::image::ImageAccess;
+use vulkano::image::ImageLayout;
+use vulkano::query::{QueryType, UnsafeQueryPool};
+use vulkano::sync::{AccessCheckError, AccessFlagBits, GpuFuture, PipelineStages};
+
+// Internal modules
+use crate::error::AppError;
+
+/// Frames in flight which can be measured at once, one per swapchain image.
+/// Frames drawn into images past that count aren't measured.
+const SLOT_COUNT: usize = 8;
+
+/// Number of measured frames the GPU time is averaged over
+const AVERAGE_WINDOW: usize = 60;
+
+/// What's known of the timestamps of a slot
+#[derive(Clone, Copy, Default)]
+struct Slot {
+    /// The queries hold the timestamps of the last frame using the slot
+    written: bool,
+    /// `results` holds the timestamps of the frame before, copied by the last
+    /// one
+    copied: bool,
+}
+
+/// Measures how long the GPU takes to run each frame, with a timestamp
+/// written before and after its commands.
+///
+/// vulkano 0.13 has no safe way of recording queries, nor of reading their
+/// results back, so the timestamps are recorded into unsafe command buffers
+/// submitted around each frame's. A frame's timestamps are only read once the
+/// swapchain image it was drawn into comes around again, the CPU has then
+/// waited for it to be done: the next frame drawn into that image copies them
+/// into a buffer the one after reads, without ever stalling.
+pub struct GpuTimer {
+    device: Arc<Device>,
+    queue: Arc<Queue>,
+    /// Two timestamps per slot, the start and end of its frame
+    query_pool: Arc<UnsafeQueryPool>,
+    /// Where the timestamps are copied to, 32 bits each as that's all vulkano
+    /// can copy
+    results: Arc<CpuAccessibleBuffer<[u32]>>,
+    slots: [Slot; SLOT_COUNT],
+    /// Keeps the bits of a timestamp difference the device counts
+    valid_bits_mask: u32,
+    /// Nanoseconds per timestamp tick
+    timestamp_period: f32,
+    /// Last frame times in milliseconds, oldest first
+    frame_times: VecDeque<f32>,
+}
+
+impl GpuTimer {
+    /// Returns `None`, with a warning, when the device can't write timestamps
+    /// from the graphics queue
+    pub fn new(device: Arc<Device>, queue: Arc<Queue>) -> Result<Option<Self>, AppError> {
+        let limits = device.physical_device().limits();
+        let timestamp_period = limits.timestamp_period();
+
+        let valid_bits = match queue.family().timestamp_valid_bits() {
+            Some(valid_bits) if limits.timestamp_compute_and_graphics() != 0 => valid_bits,
+            _ => {
+                println!("Warning: timestamps are not supported, the GPU time won't be measured");
+                return Ok(None);
+            },
+        };
+
+        let query_pool =
+            Arc::new(UnsafeQueryPool::new(device.clone(), QueryType::Timestamp, 2 * SLOT_COUNT as u32)?);
+
+        let results =
+            CpuAccessibleBuffer::from_iter(
+                device.clone(), BufferUsage { transfer_destination: true, .. BufferUsage::none() },
+                (0 .. 2 * SLOT_COUNT).map(|_| 0u32)
+            )?;
+
+        Ok(Some(GpuTimer {
+            device,
+            queue,
+            query_pool,
+            results,
+            slots: [Slot::default(); SLOT_COUNT],
+            valid_bits_mask: if valid_bits >= 32 { u32::MAX } else { (1 << valid_bits) - 1 },
+            timestamp_period,
+            frame_times: VecDeque::with_capacity(AVERAGE_WINDOW),
+        }))
+    }
+
+    /// Records the commands submitted before those of the frame drawn into the
+    /// swapchain image `image_num`, which the GPU must be done with. `None`
+    /// when that image can't be measured.
+    pub fn begin_frame(&mut self, image_num: usize) -> Result<Option<TimestampCommands>, AppError> {
+        let slot = match self.slots.get(image_num) {
+            Some(&slot) => slot,
+            None => return Ok(None),
+        };
+
+        let first_query = 2 * image_num as u32;
+        let results = self.results.clone().into_buffer_slice().slice(2 * image_num .. 2 * image_num + 2)
+            .expect("the results buffer has two timestamps per slot");
+
+        // The previous frame drawn into this image copied the timestamps of the
+        // one before, its fence was waited for so they're there
+        if slot.copied {
+            let timestamps = self.results.read()?;
+            let (start, end) = (timestamps[2 * image_num], timestamps[2 * image_num + 1]);
+
+            // 32 bits wrap around every few seconds at best, the difference of
+            // both is still right as long as the frame takes less
+            let ticks = end.wrapping_sub(start) & self.valid_bits_mask;
+            let milliseconds = ticks as f32 * self.timestamp_period / 1_000_000.0;
+
+            if self.frame_times.len() == AVERAGE_WINDOW {
+                self.frame_times.pop_front();
+            }
+            self.frame_times.push_back(milliseconds);
+        }
+
+        let commands = self.record(|builder, query_pool| unsafe {
+            if slot.written {
+                let written = query_pool.queries_range(first_query, 2)
+                    .expect("the pool has two queries per slot");
+                builder.copy_query_pool_results(written, &results, 4);
+            }
+
+            builder.reset_query_pool(
+                query_pool.queries_range(first_query, 2).expect("the pool has two queries per slot")
+            );
+            builder.write_timestamp(
+                query_pool.query(first_query).expect("the pool has two queries per slot"),
+                PipelineStages { top_of_pipe: true, .. PipelineStages::none() }
+            );
+        })?;
+
+        self.slots[image_num] = Slot { written: false, copied: slot.written };
+
+        Ok(Some(commands))
+    }
+
+    /// Whether frames drawn into the swapchain image `image_num` are measured
+    pub fn is_measuring(&self, image_num: usize) -> bool {
+        image_num < SLOT_COUNT
+    }
+
+    /// Records the commands submitted after those of the frame drawn into the
+    /// swapchain image `image_num`, once `begin_frame` returned some for it
+    pub fn end_frame(&mut self, image_num: usize) -> Result<TimestampCommands, AppError> {
+        let query = 2 * image_num as u32 + 1;
+
+        let commands = self.record(|builder, query_pool| unsafe {
+            builder.write_timestamp(
+                query_pool.query(query).expect("the pool has two queries per slot"),
+                PipelineStages { bottom_of_pipe: true, .. PipelineStages::none() }
+            );
+        })?;
+
+        // Copied by the next frame drawn into that image
+        self.slots[image_num].written = true;
+
+        Ok(commands)
+    }
+
+    /// Average GPU time of the last measured frames, in milliseconds
+    pub fn average_frame_time(&self) -> Option<f32> {
+        if self.frame_times.is_empty() {
+            return None;
+        }
+
+        Some(self.frame_times.iter().sum::<f32>() / self.frame_times.len() as f32)
+    }
+
+    /// GPU time of the last measured frame, in milliseconds, which was drawn
+    /// as many frames ago as there are swapchain images
+    pub fn last_frame_time(&self) -> Option<f32> {
+        self.frame_times.back().copied()
+    }
+
+    fn record<F>(&self, commands: F) -> Result<TimestampCommands, AppError>
+        where F: FnOnce(&mut UnsafeCommandBufferBuilder<StandardCommandPoolBuilder>, &UnsafeQueryPool)
+    {
+        let pool = Device::standard_command_pool(&self.device, self.queue.family());
+
+        // Safe as long as the queries and buffer used outlive the command
+        // buffer, which keeps them alive, and the frames using the same slot
+        // don't run at the same time, which waiting for their image ensures
+        let inner = unsafe {
+            let mut builder = UnsafeCommandBufferBuilder::new(&pool, Kind::primary(), Flags::OneTimeSubmit)?;
+            commands(&mut builder, &self.query_pool);
+            builder.build()?
+        };
+
+        Ok(TimestampCommands {
+            inner,
+            device: self.device.clone(),
+            _query_pool: self.query_pool.clone(),
+            _results: self.results.clone(),
+        })
+    }
+}
+
+/// Commands reading or writing timestamps, which vulkano doesn't track: they
+/// claim no access to any resource, leaving the futures around them to answer
+pub struct TimestampCommands {
+    inner: UnsafeCommandBuffer<StandardCommandPoolAlloc>,
+    device: Arc<Device>,
+    _query_pool: Arc<UnsafeQueryPool>,
+    _results: Arc<CpuAccessibleBuffer<[u32]>>,
+}
+
+unsafe impl DeviceOwned for TimestampCommands {
+    fn device(&self) -> &Arc<Device> {
+        &self.device
+    }
+}
+
+unsafe impl CommandBuffer for TimestampCommands {
+    type PoolAlloc = StandardCommandPoolAlloc;
+
+    fn inner(&self) -> &UnsafeCommandBuffer<StandardCommandPoolAlloc> {
+        &self.inner
+    }
+
+    fn lock_submit(&self, _future: &dyn GpuFuture, _queue: &Queue) -> Result<(), CommandBufferExecError> {
+        Ok(())
+    }
+
+    unsafe fn unlock(&self) {}
+
+    fn check_buffer_access(&self, _buffer: &dyn BufferAccess, _exclusive: bool, _queue: &Queue)
+        -> Result<Option<(PipelineStages, AccessFlagBits)>, AccessCheckError>
+    {
+        Err(AccessCheckError::Unknown)
+    }
+
+    fn check_image_access(
+        &self,
+        _image: &dyn ImageAccess,
+        _layout: ImageLayout,
+        _exclusive: bool,
+        _queue: &Queue
+    ) -> Result<Option<(PipelineStages, AccessFlagBits)>, AccessCheckError> {
+        Err(AccessCheckError::Unknown)
+    }
+}
@@ -0,0 +1,198 @@
+// Build-in modules
+use std::sync::Arc;
+use std::time::Instant;
+
+// External modules
+use vulkano::command_buffer::AutoCommandBufferBuilder;
+use vulkano::descriptor::descriptor_set::PersistentDescriptorSet;
+use vulkano::device::DeviceExtensions;
+use vulkano::format::Format;
+use vulkano::format::FormatTy;
+use vulkano::image::Dimensions;
+use vulkano::image::ImageUsage;
+use vulkano::image::StorageImage;
+use vulkano::pipeline::ComputePipeline;
+use vulkano::sampler::Filter;
+use vulkano::swapchain::AcquireError;
+use vulkano::swapchain::SwapchainCreationError;
+use vulkano::swapchain;
+use vulkano::sync::FlushError;
+use vulkano::sync::GpuFuture;
+use vulkano::sync;
+use vulkano_win::VkSurfaceBuild;
+use winit::ElementState;
+use winit::EventsLoop;
+use winit::KeyboardInput;
+use winit::VirtualKeyCode;
+use winit::WindowBuilder;
+
+// Internal modules
+use crate::config::Config;
+use crate::error::AppError;
+use crate::renderer::{self, SwapchainUsage, VulkanContext};
+
+/// Size of the storage image the gradient is computed into, a multiple of the
+/// shader's 8x8 groups. Smaller than the window, so the blit scales it up.
+const WIDTH: u32 = 256;
+const HEIGHT: u32 = 128;
+
+mod cs {
+    vulkano_shaders::shader!{
+        ty: "compute",
+        path: "src/shaders/gradient.comp"
+    }
+}
+
+/// Fills the window every frame without any render pass: a compute shader
+/// writes a gradient into a storage image, which is then blitted into the
+/// swapchain image, scaled up with linear filtering. Contrasts with the clear
+/// values render passes start from.
+pub fn run(config: &Config) -> Result<(), AppError> {
+    let context = VulkanContext::new(vulkano_win::required_extensions())?;
+    let extensions = DeviceExtensions { khr_swapchain: true, .. DeviceExtensions::none() };
+    let (device, queue, _) = context.open_device(config, &extensions)?;
+    let physical_device = device.physical_device();
+
+    if !queue.family().supports_compute() {
+        return Err(AppError::NoSuitableDevice("the graphics queue doesn't support compute".into()));
+    }
+
+    let mut events_loop = EventsLoop::new();
+    let surface =
+        WindowBuilder::new()
+            .with_dimensions((config.width, config.height).into())
+            .with_title(config.title.clone())
+            .build_vk_surface(&events_loop, context.instance.clone())?;
+
+    let capabilities = surface.capabilities(physical_device)?;
+    let swapchain_format = renderer::choose_swapchain_format(&capabilities, config.swapchain_encoding);
+
+    // Only ever blitted into, never drawn into
+    let usage =
+        SwapchainUsage {
+            needed: ImageUsage { transfer_destination: true, .. ImageUsage::none() },
+            optional: ImageUsage::none(),
+        };
+    let (mut swapchain, mut images) =
+        renderer::create_swapchain(
+            &device, &queue, surface.clone(), swapchain_format, config.present_mode, config.image_count, usage
+        )?;
+
+    let image =
+        StorageImage::with_usage(
+            device.clone(),
+            Dimensions::Dim2d { width: WIDTH, height: HEIGHT },
+            blit_source_format(swapchain_format)?,
+            ImageUsage { storage: true, transfer_source: true, .. ImageUsage::none() },
+            Some(queue.family())
+        )?;
+
+    let shader = cs::Shader::load(device.clone())?;
+    let pipeline = Arc::new(ComputePipeline::new(device.clone(), &shader.main_entry_point(), &())?);
+    let set =
+        Arc::new(
+            PersistentDescriptorSet::start(pipeline.clone(), 0)
+                .add_image(image.clone())?
+                .build()?
+        );
+
+    let start_time = Instant::now();
+    let mut previous_frame_end = Box::new(sync::now(device.clone())) as Box<dyn GpuFuture>;
+    let mut swapchain_outdated = false;
+
+    loop {
+        previous_frame_end.cleanup_finished();
+
+        if swapchain_outdated {
+            let capabilities = surface.capabilities(physical_device)?;
+            let dimensions = renderer::get_dimensions(&surface, &capabilities);
+
+            match swapchain.recreate_with_dimension(dimensions) {
+                Ok((new_swapchain, new_images)) => {
+                    swapchain = new_swapchain;
+                    images = new_images;
+                    swapchain_outdated = false;
+                },
+                // Happens while the user is resizing the window, try again next frame
+                Err(SwapchainCreationError::UnsupportedDimensions) => (),
+                Err(err) => return Err(err.into()),
+            }
+        }
+
+        if !swapchain_outdated {
+            match swapchain::acquire_next_image(swapchain.clone(), None) {
+                Ok((image_num, acquire_future)) => {
+                    let [width, height] = images[image_num].dimensions();
+                    let time = start_time.elapsed().as_secs_f32();
+
+                    // vulkano moves the storage image from the general layout the
+                    // shader writes in to the one blits read from, and the
+                    // swapchain image to the one they write to then back to the
+                    // one it's presented in
+                    let command_buffer =
+                        AutoCommandBufferBuilder::primary_one_time_submit(device.clone(), queue.family())?
+                            .dispatch(
+                                [WIDTH / 8, HEIGHT / 8, 1], pipeline.clone(), set.clone(),
+                                cs::ty::PushConstants { time }
+                            )?
+                            .blit_image(
+                                image.clone(), [0, 0, 0], [WIDTH as i32, HEIGHT as i32, 1], 0, 0,
+                                images[image_num].clone(), [0, 0, 0], [width as i32, height as i32, 1], 0, 0,
+                                1, Filter::Linear
+                            )?
+                            .build()?;
+
+                    let future =
+                        previous_frame_end
+                            .join(acquire_future)
+                            .then_execute(queue.clone(), command_buffer)?
+                            .then_swapchain_present(queue.clone(), swapchain.clone(), image_num)
+                            .then_signal_fence_and_flush();
+
+                    previous_frame_end = match future {
+                        Ok(future) => Box::new(future),
+                        Err(FlushError::OutOfDate) => {
+                            swapchain_outdated = true;
+                            Box::new(sync::now(device.clone()))
+                        },
+                        Err(err) => return Err(err.into()),
+                    };
+                },
+                Err(AcquireError::OutOfDate) => swapchain_outdated = true,
+                Err(err) => return Err(err.into()),
+            }
+        }
+
+        let mut done = false;
+        events_loop.poll_events(|event| {
+            if let winit::Event::WindowEvent { event, .. } = event {
+                match event {
+                    winit::WindowEvent::CloseRequested
+                    | winit::WindowEvent::KeyboardInput {
+                        input: KeyboardInput {
+                            state: ElementState::Pressed, virtual_keycode: Some(VirtualKeyCode::Escape), ..
+                        },
+                        ..
+                    } => done = true,
+                    winit::WindowEvent::Resized(_) | winit::WindowEvent::HiDpiFactorChanged(_) =>
+                        swapchain_outdated = true,
+                    _ => (),
+                }
+            }
+        });
+        if done {
+            return Ok(());
+        }
+    }
+}
+
+/// The format the shader writes the gradient in when it can be blitted into
+/// images of `swapchain_format`. Blits convert between formats, but only among
+/// floating point and normalized ones, never to integer ones.
+fn blit_source_format(swapchain_format: Format) -> Result<Format, AppError> {
+    match swapchain_format.ty() {
+        // Storage images can't be sRGB, a linear one is encoded by the blit
+        FormatTy::Float => Ok(Format::R8G8B8A8Unorm),
+        _ => Err(AppError::NoSuitableDevice(format!("can't blit into {:?} images", swapchain_format))),
+    }
+}
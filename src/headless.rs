@@ -0,0 +1,130 @@
+// Build-in modules
+use std::sync::Arc;
+
+// External modules
+use image::{ImageBuffer, Rgba};
+use vulkano::buffer::BufferUsage;
+use vulkano::buffer::CpuAccessibleBuffer;
+use vulkano::command_buffer::AutoCommandBufferBuilder;
+use vulkano::device::{Device, DeviceExtensions, Queue};
+use vulkano::format::Format;
+use vulkano::image::Dimensions;
+use vulkano::image::ImageUsage;
+use vulkano::image::StorageImage;
+use vulkano::instance::InstanceExtensions;
+use vulkano::sync::GpuFuture;
+use vulkano::sync;
+
+// Internal modules
+use crate::config::Config;
+use crate::error::AppError;
+use crate::histogram;
+use crate::renderer::VulkanContext;
+use crate::scene::{Scene, Targets};
+
+const WIDTH: u32 = 1280;
+const HEIGHT: u32 = 1024;
+
+/// sRGB encoded like the PNG files, so that the bytes can be saved as-is
+const FORMAT: Format = Format::R8G8B8A8Srgb;
+
+/// Animation time between two frames, in seconds. Fixed instead of measured so
+/// that every run produces the same images.
+const TIMESTEP: f32 = 1.0 / 60.0;
+
+/// Draws the scene `config` describes offscreen, into an image of a fixed size
+/// without any window or surface, reading every frame back
+pub struct HeadlessRenderer {
+    _context: VulkanContext,
+    device: Arc<Device>,
+    queue: Arc<Queue>,
+    scene: Scene,
+    targets: Targets,
+    image: Arc<StorageImage<Format>>,
+    /// Where the image is copied to, to be read by the CPU
+    buffer: Arc<CpuAccessibleBuffer<[u8]>>,
+    /// Number of the next frame drawn
+    frame: u32,
+}
+
+impl HeadlessRenderer {
+    pub fn new(config: &Config) -> Result<Self, AppError> {
+        let context = VulkanContext::new(InstanceExtensions::none())?;
+        let (device, queue, transfer_queue) = context.open_scene_device(config, &DeviceExtensions::none())?;
+
+        let image =
+            StorageImage::with_usage(
+                device.clone(), Dimensions::Dim2d { width: WIDTH, height: HEIGHT }, FORMAT,
+                ImageUsage { color_attachment: true, transfer_source: true, .. ImageUsage::none() },
+                Some(queue.family())
+            )?;
+
+        let mut scene = Scene::from_config(device.clone(), queue.clone(), transfer_queue, FORMAT, config)?;
+        let targets = scene.create_targets(std::slice::from_ref(&image))?;
+
+        if let Some(uploads) = scene.take_pending_uploads() {
+            uploads.then_signal_fence_and_flush()?.wait(None)?;
+        }
+
+        let buffer =
+            CpuAccessibleBuffer::from_iter(
+                device.clone(), BufferUsage::all(),
+                (0 .. WIDTH * HEIGHT * 4).map(|_| 0u8)
+            )?;
+
+        Ok(HeadlessRenderer { _context: context, device, queue, scene, targets, image, buffer, frame: 0 })
+    }
+
+    /// Draws the next frame, `TIMESTEP` further into the animation than the
+    /// previous one, and returns its `WIDTH` by `HEIGHT` pixels, row by row
+    /// from the top, as sRGB encoded RGBA bytes
+    pub fn draw_frame(&mut self) -> Result<Vec<u8>, AppError> {
+        if self.frame > 0 {
+            self.scene.update(TIMESTEP);
+        }
+        let draw_command_buffer = self.scene.draw(&mut self.targets, 0, self.frame as f32 * TIMESTEP)?;
+        self.frame += 1;
+
+        let copy_command_buffer =
+            AutoCommandBufferBuilder::new(self.device.clone(), self.queue.family())?
+                .copy_image_to_buffer(self.image.clone(), self.buffer.clone())?
+                .build()?;
+
+        sync::now(self.device.clone())
+            .then_execute(self.queue.clone(), draw_command_buffer)?
+            .then_execute(self.queue.clone(), copy_command_buffer)?
+            .then_signal_fence_and_flush()?
+            .wait(None)?;
+
+        Ok(self.buffer.read()?.to_vec())
+    }
+
+    /// The luminance histogram of the frame last drawn, `None` unless
+    /// `--histogram` is set
+    pub fn read_histogram(&self) -> Result<Option<Vec<u32>>, AppError> {
+        self.scene.read_histogram(&self.targets, 0)
+    }
+}
+
+/// Renders `frame_count` frames of the scene `config` describes offscreen,
+/// saving each of them as `frame_NNNN.png`
+pub fn run(config: &Config, frame_count: u32) -> Result<(), AppError> {
+    let mut renderer = HeadlessRenderer::new(config)?;
+
+    for frame in 0 .. frame_count {
+        let pixels = renderer.draw_frame()?;
+        let frame_image =
+            ImageBuffer::<Rgba<u8>, _>::from_raw(WIDTH, HEIGHT, pixels)
+                .ok_or(AppError::InvalidImageData("buffer is too small for the image"))?;
+
+        let path = format!("frame_{:04}.png", frame);
+        frame_image.save(&path)?;
+        println!("Saved {}", path);
+
+        if let Some(bins) = renderer.read_histogram()? {
+            println!("{}", histogram::format(&bins));
+        }
+    }
+
+    Ok(())
+}
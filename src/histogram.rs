@@ -0,0 +1,144 @@
+// Build-in modules
+use std::sync::Arc;
+
+// External modules
+use vulkano::buffer::BufferUsage;
+use vulkano::buffer::CpuAccessibleBuffer;
+use vulkano::command_buffer::AutoCommandBufferBuilder;
+use vulkano::descriptor::DescriptorSet;
+use vulkano::descriptor::descriptor_set::PersistentDescriptorSet;
+use vulkano::descriptor::pipeline_layout::PipelineLayout;
+use vulkano::device::Device;
+use vulkano::image::ImageViewAccess;
+use vulkano::pipeline::ComputePipeline;
+use vulkano::sampler::{Filter, MipmapMode, Sampler, SamplerAddressMode};
+
+// Internal modules
+use crate::error::AppError;
+
+/// Number of luminance ranges counted, as in the shader
+pub const BIN_COUNT: usize = 64;
+
+/// Width and height of the pixel blocks the shader works on
+const GROUP_SIZE: u32 = 16;
+
+/// Levels the bins are printed with, from empty to the fullest
+const BAR_LEVELS: [char; 9] = [' ', '▁', '▂', '▃', '▄', '▅', '▆', '▇', '█'];
+
+mod cs {
+    vulkano_shaders::shader!{
+        ty: "compute",
+        path: "src/shaders/histogram.comp"
+    }
+}
+
+/// Counts the pixels of an image by luminance with a compute shader, a first
+/// step towards adjusting the exposure to the scene
+pub struct Histogram {
+    device: Arc<Device>,
+    pipeline: Arc<ComputePipeline<PipelineLayout<cs::Layout>>>,
+    /// Reads the image pixel per pixel
+    sampler: Arc<Sampler>,
+}
+
+impl Histogram {
+    pub fn new(device: Arc<Device>) -> Result<Self, AppError> {
+        let shader = cs::Shader::load(device.clone())?;
+        let pipeline = Arc::new(ComputePipeline::new(device.clone(), &shader.main_entry_point(), &())?);
+
+        let sampler =
+            Sampler::new(
+                device.clone(),
+                Filter::Nearest, Filter::Nearest, MipmapMode::Nearest,
+                SamplerAddressMode::ClampToEdge, SamplerAddressMode::ClampToEdge,
+                SamplerAddressMode::ClampToEdge,
+                0.0, 1.0, 0.0, 0.0
+            )?;
+
+        Ok(Histogram { device, pipeline, sampler })
+    }
+
+    /// Prepares counting the pixels of `image`, of `dimensions`, into a buffer
+    /// of its own
+    pub fn create_target<I>(&self, image: I, dimensions: [u32; 2]) -> Result<HistogramTarget, AppError>
+        where I: ImageViewAccess + Send + Sync + 'static
+    {
+        // Zeroed by the GPU before every count
+        let bins =
+            CpuAccessibleBuffer::from_iter(
+                self.device.clone(),
+                BufferUsage { storage_buffer: true, transfer_destination: true, .. BufferUsage::none() },
+                (0 .. BIN_COUNT).map(|_| 0u32)
+            )?;
+
+        let set =
+            Arc::new(
+                PersistentDescriptorSet::start(self.pipeline.clone(), 0)
+                    .add_sampled_image(image, self.sampler.clone())?
+                    .add_buffer(bins.clone())?
+                    .build()?
+            );
+
+        Ok(HistogramTarget { bins, set, dimensions })
+    }
+
+    /// Records zeroing the bins of `target` then counting its image into them.
+    /// Must be recorded outside of any render pass.
+    ///
+    /// The image is last written as a color attachment, reading it from the
+    /// compute shader needs a barrier from those writes and a transition into
+    /// a layout shaders can sample. vulkano tracks both accesses and inserts
+    /// them itself, as it does between the fill and the atomic adds.
+    pub fn record(&self, builder: AutoCommandBufferBuilder, target: &HistogramTarget)
+        -> Result<AutoCommandBufferBuilder, AppError>
+    {
+        let [width, height] = target.dimensions;
+        let group_count = [width.div_ceil(GROUP_SIZE), height.div_ceil(GROUP_SIZE), 1];
+
+        Ok(builder
+            .fill_buffer(target.bins.clone(), 0)?
+            .dispatch(group_count, self.pipeline.clone(), target.set.clone(), ())?)
+    }
+}
+
+/// An image along with the bins its pixels are counted into
+pub struct HistogramTarget {
+    bins: Arc<CpuAccessibleBuffer<[u32]>>,
+    set: Arc<dyn DescriptorSet + Send + Sync>,
+    dimensions: [u32; 2],
+}
+
+impl HistogramTarget {
+    /// Copies the bins counted by the last frame, which the GPU must be done
+    /// with
+    pub fn read(&self) -> Result<Vec<u32>, AppError> {
+        Ok(self.bins.read()?.to_vec())
+    }
+}
+
+/// Formats `bins` as a bar per bin, from the darkest to the brightest, scaled
+/// to the fullest one, followed by the average luminance. Empty when nothing
+/// was counted yet.
+pub fn format(bins: &[u32]) -> String {
+    let total = bins.iter().map(|&count| count as u64).sum::<u64>();
+    let fullest = bins.iter().copied().max().unwrap_or(0);
+    if total == 0 {
+        return String::new();
+    }
+
+    let bars =
+        bins.iter()
+            .map(|&count| {
+                let level = (count as u64 * (BAR_LEVELS.len() - 1) as u64).div_ceil(fullest as u64);
+                BAR_LEVELS[level as usize]
+            })
+            .collect::<String>();
+
+    // Each bin stands for the luminance in its middle
+    let average =
+        bins.iter().enumerate()
+            .map(|(index, &count)| (index as f64 + 0.5) / bins.len() as f64 * count as f64)
+            .sum::<f64>() / total as f64;
+
+    format!("|{}| average luminance {:.3}", bars, average)
+}
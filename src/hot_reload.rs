@@ -0,0 +1,176 @@
+// Build-in modules
+use std::fmt;
+use std::fs;
+use std::path::Path;
+use std::sync::mpsc::{self, Receiver};
+use std::time::Duration;
+
+// External modules
+use notify::{DebouncedEvent, RecommendedWatcher, RecursiveMode, Watcher};
+use shaderc::{Compiler, ShaderKind};
+
+// Internal modules
+use crate::error::AppError;
+
+/// Editors often write a file in several steps, the events of a burst are
+/// merged into one
+const DEBOUNCE_DELAY: Duration = Duration::from_millis(200);
+
+/// The shader sources, where they're edited rather than where the executable
+/// ends up
+pub const SHADERS_DIR: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/src/shaders");
+
+/// Tells when any file of a directory changes
+pub struct ShaderWatcher {
+    // Stops watching once dropped
+    _watcher: RecommendedWatcher,
+    events: Receiver<DebouncedEvent>,
+}
+
+impl ShaderWatcher {
+    pub fn new(dir: &str) -> Result<Self, AppError> {
+        let (sender, events) = mpsc::channel();
+
+        let mut watcher = notify::watcher(sender, DEBOUNCE_DELAY)?;
+        watcher.watch(dir, RecursiveMode::Recursive)?;
+
+        Ok(ShaderWatcher { _watcher: watcher, events })
+    }
+
+    /// Whether a file was written, created or renamed since the last call
+    pub fn changed(&self) -> bool {
+        // Drains every event, so that a burst only counts once
+        let changes =
+            self.events.try_iter()
+                .filter(|event| matches!(
+                    event,
+                    DebouncedEvent::Write(_) | DebouncedEvent::Create(_) | DebouncedEvent::Rename(_, _)
+                ))
+                .count();
+
+        changes > 0
+    }
+}
+
+/// One error the shader compiler reported
+#[derive(Debug)]
+pub struct ShaderDiagnostic {
+    pub file: String,
+    /// `None` for errors about the whole file
+    pub line: Option<u32>,
+    pub message: String,
+}
+
+/// Every error the shader compiler reported for a source, one per line once
+/// displayed
+#[derive(Debug)]
+pub struct ShaderDiagnostics(pub Vec<ShaderDiagnostic>);
+
+impl fmt::Display for ShaderDiagnostics {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for (index, diagnostic) in self.0.iter().enumerate() {
+            if index > 0 {
+                writeln!(f)?;
+            }
+
+            match diagnostic.line {
+                Some(line) => write!(f, "    {}:{}: {}", diagnostic.file, line, diagnostic.message)?,
+                None => write!(f, "    {}: {}", diagnostic.file, diagnostic.message)?,
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// Compiles the GLSL shader at `path` into SPIR-V, the way `vulkano_shaders`
+/// does at build time. Errors in the source come back as diagnostics, the
+/// warnings are printed.
+pub fn compile(path: &Path, kind: ShaderKind) -> Result<Vec<u32>, AppError> {
+    let source = fs::read_to_string(path)?;
+    let file = path.to_string_lossy();
+
+    let mut compiler = Compiler::new().ok_or(AppError::ShaderCompilerCreation)?;
+    let artifact =
+        match compiler.compile_into_spirv(&source, kind, &file, "main", None) {
+            Ok(artifact) => artifact,
+            Err(shaderc::Error::CompilationError(_, log)) =>
+                return Err(AppError::ShaderSource(parse_diagnostics(&file, &log))),
+            Err(err) => return Err(err.into()),
+        };
+
+    if artifact.get_num_warnings() > 0 {
+        println!("Warning: {}", artifact.get_warning_messages().trim_end());
+    }
+
+    Ok(artifact.as_binary().to_vec())
+}
+
+/// Splits the `log` of errors shaderc gives for `file` into diagnostics. Its
+/// lines look like `<file>:<line>: error: <message>`, or without the line for
+/// errors about the whole file. Other lines, such as the error count, are left
+/// out, unless none of them matched.
+fn parse_diagnostics(file: &str, log: &str) -> ShaderDiagnostics {
+    let diagnostics =
+        log.lines()
+            .filter_map(|line| {
+                let (location, message) = line.split_once(": error: ")?;
+
+                // Paths can contain colons of their own, the line number is
+                // after the last one
+                let (file, line) = match location.rsplit_once(':') {
+                    Some((file, line)) if line.parse::<u32>().is_ok() => (file, line.parse().ok()),
+                    _ => (location, None),
+                };
+
+                Some(ShaderDiagnostic { file: file.to_string(), line, message: message.trim().to_string() })
+            })
+            .collect::<Vec<_>>();
+
+    if diagnostics.is_empty() {
+        return ShaderDiagnostics(vec![
+            ShaderDiagnostic { file: file.to_string(), line: None, message: log.trim().to_string() }
+        ]);
+    }
+
+    ShaderDiagnostics(diagnostics)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// The file, line and message of each diagnostic, to compare at once
+    fn summarize(diagnostics: &ShaderDiagnostics) -> Vec<(&str, Option<u32>, &str)> {
+        diagnostics.0.iter()
+            .map(|diagnostic| (diagnostic.file.as_str(), diagnostic.line, diagnostic.message.as_str()))
+            .collect()
+    }
+
+    #[test]
+    fn parses_line_and_whole_file_errors() {
+        // As shaderc logs them, with a drive letter adding a colon to the path
+        let file = r"C:\shaders\triangle.frag";
+        let log =
+            "C:\\shaders\\triangle.frag:12: error: 'colour' : undeclared identifier\n\
+             C:\\shaders\\triangle.frag: error: Missing entry point: Each stage requires one entry point\n\
+             2 errors generated.\n";
+
+        let diagnostics = parse_diagnostics(file, log);
+
+        assert_eq!(
+            summarize(&diagnostics),
+            vec![
+                (file, Some(12), "'colour' : undeclared identifier"),
+                (file, None, "Missing entry point: Each stage requires one entry point"),
+            ]
+        );
+    }
+
+    #[test]
+    fn keeps_the_whole_log_when_nothing_matches() {
+        let diagnostics = parse_diagnostics("triangle.vert", "  internal compiler failure\n");
+
+        assert_eq!(summarize(&diagnostics), vec![("triangle.vert", None, "internal compiler failure")]);
+    }
+}
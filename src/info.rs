@@ -0,0 +1,80 @@
+// External modules
+use vulkano::swapchain::Capabilities;
+use vulkano_win::VkSurfaceBuild;
+use winit::EventsLoop;
+use winit::WindowBuilder;
+
+// Internal modules
+use crate::config::Config;
+use crate::error::AppError;
+use crate::renderer;
+
+/// Width the names of the table rows are padded to
+const LABEL_WIDTH: usize = 24;
+
+/// Prints what the surface of a window `config` describes supports on the
+/// device it would be drawn with, then exits without drawing anything. Meant
+/// to be pasted into reports of formats or present modes not working.
+pub fn run(config: &Config) -> Result<(), AppError> {
+    let instance = renderer::create_instance(vulkano_win::required_extensions())?;
+    let physical_device = renderer::choose_physical_device(&instance, config)?;
+
+    // Never shown, only there for its surface
+    let events_loop = EventsLoop::new();
+    let surface =
+        WindowBuilder::new()
+            .with_dimensions((config.width, config.height).into())
+            .with_title(config.title.clone())
+            .with_visibility(false)
+            .build_vk_surface(&events_loop, instance.clone())?;
+
+    let capabilities = surface.capabilities(physical_device)?;
+
+    println!("Surface capabilities on {}:", physical_device.name());
+    println!();
+    print_capabilities(&capabilities);
+
+    Ok(())
+}
+
+/// Prints one row per capability, with the lists below their row name
+fn print_capabilities(capabilities: &Capabilities) {
+    let row = |label: &str, value: String| println!("    {:<width$}{}", label, value, width = LABEL_WIDTH);
+    let list = |label: &str, values: Vec<String>| {
+        match values.split_first() {
+            Some((first, rest)) => {
+                row(label, first.clone());
+                for value in rest {
+                    row("", value.clone());
+                }
+            },
+            None => row(label, "none".into()),
+        }
+    };
+    let extent = |[width, height]: [u32; 2]| format!("{}x{}", width, height);
+
+    row("Min image count", capabilities.min_image_count.to_string());
+    row(
+        "Max image count",
+        capabilities.max_image_count.map_or_else(|| "unlimited".into(), |count| count.to_string())
+    );
+    row("Current extent", capabilities.current_extent.map_or_else(|| "from the window".into(), extent));
+    row("Min image extent", extent(capabilities.min_image_extent));
+    row("Max image extent", extent(capabilities.max_image_extent));
+    row("Current transform", format!("{:?}", capabilities.current_transform));
+    list(
+        "Supported transforms",
+        capabilities.supported_transforms.iter().map(|transform| format!("{:?}", transform)).collect()
+    );
+    list(
+        "Composite alpha",
+        capabilities.supported_composite_alpha.iter().map(|alpha| format!("{:?}", alpha)).collect()
+    );
+    list("Present modes", capabilities.present_modes.iter().map(|mode| format!("{:?}", mode)).collect());
+    list(
+        "Formats",
+        capabilities.supported_formats.iter()
+            .map(|(format, color_space)| format!("{:<28}{:?}", format!("{:?}", format), color_space))
+            .collect()
+    );
+}
@@ -0,0 +1,25 @@
+// Internal modules
+mod assets;
+pub mod blur;
+pub mod camera;
+pub mod config;
+pub mod debug_lines;
+pub mod deferred;
+pub mod error;
+mod gpu_timer;
+pub mod gradient;
+pub mod headless;
+mod histogram;
+#[cfg(debug_assertions)]
+pub mod hot_reload;
+pub mod info;
+pub mod mandelbrot;
+mod particles;
+pub mod profile;
+pub mod renderer;
+pub mod scene;
+mod skybox;
+mod text;
+pub mod timing;
+pub mod two_windows;
+mod uniform_ring;
@@ -1,297 +1,395 @@
 // Build-in modules
+use std::collections::HashSet;
+use std::env;
 use std::error::Error;
-use std::sync::Arc;
+use std::process::ExitCode;
+use std::thread;
+use std::time::{Duration, Instant};
 
 // External modules
-use image::{ImageBuffer, Rgba};
-use vulkano::buffer::BufferUsage;
-use vulkano::buffer::CpuAccessibleBuffer;
-use vulkano::command_buffer::AutoCommandBufferBuilder;
-use vulkano::command_buffer::CommandBuffer;
-use vulkano::command_buffer::DynamicState;
-use vulkano::descriptor::descriptor_set::PersistentDescriptorSet;
-use vulkano::device::Device;
-use vulkano::device::DeviceExtensions;
-use vulkano::device::Features;
-use vulkano::device::Queue;
-use vulkano::format::ClearValue;
-use vulkano::format::Format;
-use vulkano::framebuffer::Framebuffer;
-use vulkano::framebuffer::FramebufferAbstract;
-use vulkano::framebuffer::RenderPassAbstract;
-use vulkano::framebuffer::Subpass;
-use vulkano::image::Dimensions;
-use vulkano::image::StorageImage;
-use vulkano::image::swapchain::SwapchainImage;
-use vulkano::instance::Instance;
-use vulkano::instance::InstanceExtensions;
-use vulkano::instance::PhysicalDevice;
-use vulkano::pipeline::ComputePipeline;
-use vulkano::pipeline::GraphicsPipeline;
-use vulkano::pipeline::viewport::Viewport;
-use vulkano::swapchain::Capabilities;
-use vulkano::swapchain::Surface;
-use vulkano::swapchain::{Swapchain, SurfaceTransform, PresentMode};
-use vulkano::swapchain;
-use vulkano::sync::GpuFuture;
-use vulkano_win::VkSurfaceBuild;
+use cgmath::Deg;
+use winit::ControlFlow;
+use winit::ElementState;
 use winit::EventsLoop;
-use winit::Window;
-use winit::WindowBuilder;
+use winit::KeyboardInput;
+use winit::VirtualKeyCode;
+
+// Internal modules
+use testing_rust::blur;
+use testing_rust::camera::Camera;
+use testing_rust::config::{self, Config, Mode};
+use testing_rust::debug_lines::DebugLines;
+use testing_rust::deferred;
+use testing_rust::error::AppError;
+use testing_rust::gradient;
+use testing_rust::headless;
+#[cfg(debug_assertions)]
+use testing_rust::hot_reload;
+use testing_rust::info;
+use testing_rust::mandelbrot;
+use testing_rust::profile;
+use testing_rust::renderer::Renderer;
+use testing_rust::scene;
+use testing_rust::timing::FrameTimer;
+use testing_rust::two_windows;
+
+/// How far the camera turns for every pixel the mouse moves by
+const MOUSE_SENSITIVITY: Deg<f32> = Deg(0.1);
+
+/// How often the FPS shown over the scene are refreshed, updating them every
+/// frame would make them flicker
+const STATS_UPDATE_INTERVAL: Duration = Duration::from_millis(250);
+
+/// Where the FPS are shown, in pixels from the top left corner of the scene
+const STATS_POSITION: (f32, f32) = (8.0, 8.0);
+
+/// Pixels the lines widen or narrow by on every press of ] or [
+const LINE_WIDTH_STEP: f32 = 1.0;
+
+/// Frame rate the rendering is capped to by default when presenting doesn't
+/// wait for the display. Fixed rather than the display's refresh rate, which
+/// winit 0.19 doesn't report: it only knows the size and position of the
+/// monitors, their video modes came with later versions.
+const DEFAULT_MAX_FPS: u32 = 60;
+
+/// Length of the steps the scene is updated by, in seconds, whatever the
+/// frame rate
+const TIMESTEP: f32 = 1.0 / 120.0;
+
+/// Most time a frame catches up on with updates, in seconds. Past that, as
+/// after a hitch, the simulation slows down instead of spending ever longer
+/// updating.
+const MAX_FRAME_TIME: f32 = 0.25;
+
+/// How often events are polled while the window is minimized, keeping it
+/// responsive without spinning
+const PAUSED_POLL_INTERVAL: Duration = Duration::from_millis(16);
+
+fn main() -> ExitCode {
+    let err = match run() {
+        Ok(()) => return ExitCode::SUCCESS,
+        Err(err) => err,
+    };
 
-#[derive(Default, Copy, Clone)]
-struct Vertex {
-    position: [f32; 2],
-}
-vulkano::impl_vertex!(Vertex, position);
-
-fn main() -> Result<(), Box<Error>> {
-    let (
-        instance, device, queue,
-        surface, capabilities, mut events_loop
-    ) = init()?;
-
-
-    let dimensions = capabilities.current_extent.unwrap_or([1280, 1024]);
-    let alpha = capabilities.supported_composite_alpha.iter().next().unwrap();
-    let format = capabilities.supported_formats[0].0;
-
-    let (swapchain, images) =
-        Swapchain::new(
-            device.clone(), surface.clone(), capabilities.min_image_count,
-            format, dimensions, 1, capabilities.supported_usage_flags, &queue,
-            SurfaceTransform::Identity, alpha, PresentMode::Fifo, true, None
-        )?;
-
-    let vertex1 = Vertex { position: [-0.5, -0.5] };
-    let vertex2 = Vertex { position: [ 0.0,  0.5] };
-    let vertex3 = Vertex { position: [ 0.5, -0.25] };
-
-    let vertex_buffer =
-        CpuAccessibleBuffer::from_iter(
-            device.clone(), BufferUsage::all(),
-            vec![vertex1, vertex2, vertex3].into_iter()
-        )?;
-
-    mod vs {
-        vulkano_shaders::shader!{
-            ty: "vertex",
-            src: "
-#version 450
-
-layout(location = 0) in vec2 position;
-
-void main() {
-    gl_Position = vec4(position, 0.0, 1.0);
-}"
-        }
+    eprintln!("Error: {}", err);
+
+    let mut source = err.source();
+    while let Some(cause) = source {
+        eprintln!("  caused by: {}", cause);
+        source = cause.source();
+    }
+
+    if let AppError::InvalidArgument(_) = err {
+        eprintln!("{}", config::USAGE);
     }
 
-    mod fs {
-        vulkano_shaders::shader!{
-            ty: "fragment",
-            src: "
-#version 450
+    if let Some(hint) = err.hint() {
+        eprintln!();
+        eprintln!("{}", hint);
+    }
 
-layout(location = 0) out vec4 f_color;
+    ExitCode::FAILURE
+}
 
-void main() {
-    f_color = vec4(1.0, 0.0, 0.0, 1.0);
-}"
-        }
+fn run() -> Result<(), AppError> {
+    let args: Vec<String> = env::args().collect();
+    let config = match Config::from_args(&args)? {
+        Some(config) => config,
+        None => {
+            println!("{}", config::USAGE);
+            println!();
+            println!("{}", config::HELP);
+            return Ok(());
+        },
+    };
+
+    match &config.mode {
+        Mode::Window => (),
+        Mode::Mandelbrot { verify } => return mandelbrot::run(&config, *verify),
+        Mode::Gradient => return gradient::run(&config),
+        Mode::Deferred => return deferred::run(&config),
+        Mode::Headless { frame_count } => return headless::run(&config, *frame_count),
+        Mode::BlurImage { path, radius } => return blur::run(&config, path, *radius),
+        Mode::TwoWindows => return two_windows::run(&config),
+        Mode::Info => return info::run(&config),
     }
 
-    let vs = vs::Shader::load(device.clone())?;
-    let fs = fs::Shader::load(device.clone())?;
-
-    let render_pass =
-        Arc::new(
-            vulkano::single_pass_renderpass!(
-                device.clone(),
-                attachments: {
-                    color: {
-                        load: Clear,
-                        store: Store,
-                        format: swapchain.format(),
-                        samples: 1,
-                    }
-                },
-                pass: {
-                    color: [color],
-                    depth_stencil: {}
-                }
-            )?
-        );
-
-    let pipeline =
-        Arc::new(
-            GraphicsPipeline::start()
-                // Defines what kind of vertex input is expected.
-                .vertex_input_single_buffer::<Vertex>()
-                // The vertex shader.
-                .vertex_shader(vs.main_entry_point(), ())
-                // Defines the viewport.
-                .viewports_dynamic_scissors_irrelevant(1)
-                // The fragment shader.
-                .fragment_shader(fs.main_entry_point(), ())
-                // This graphics pipeline object concerns the first pass of the render pass.
-                .render_pass(Subpass::from(render_pass.clone(), 0).unwrap())
-                // Now that everything is specified, we call `build`.
-                .build(device.clone())?
-        );
-
-    let mut dynamic_state =
-        DynamicState {
-            viewports: Some(vec![Viewport {
-                origin: [0.0, 0.0],
-                dimensions: [1024.0, 1024.0],
-                depth_range: 0.0 .. 1.0,
-            }]),
-            .. DynamicState::none()
-        };
+    let mut events_loop = EventsLoop::new();
+    let mut renderer = Renderer::new(&config, &events_loop)?;
+    let mut recovered_device_loss = false;
+
+    // Declared before the loop so that it's dropped, flushing the rows, when
+    // the loop returns early with an error too
+    let mut profiler = match &config.profile_path {
+        Some(path) => Some(profile::Profiler::create(path, config.record_threads.unwrap_or(1))?),
+        None => None,
+    };
 
-    let mut framebuffers =
-        window_size_dependent_setup(
-            &images,
-            render_pass.clone(),
-            &mut dynamic_state
-        );
+    let max_fps_duration = config.max_fps.map(|fps| Duration::from_secs(1) / fps);
+    // Drawing faster than most displays refresh only heats up the GPU
+    let default_frame_duration =
+        if config.uncapped { None } else { Some(Duration::from_secs(1) / DEFAULT_MAX_FPS) };
+
+    // Shader edits show up without restarting in debug builds
+    #[cfg(debug_assertions)]
+    let shader_watcher = match hot_reload::ShaderWatcher::new(hot_reload::SHADERS_DIR) {
+        Ok(watcher) => Some(watcher),
+        Err(err) => {
+            println!("Warning: {}, they won't be reloaded", err);
+            None
+        },
+    };
+
+    // Rewritten every frame from their initial positions, unless the GPU
+    // already animates them
+    let wave_vertices = if config.wave && !config.gpu_wave { Some(renderer.read_vertices()?) } else { None };
+
+    let mut camera = Camera::new([0.0, 0.0, 2.0], MOUSE_SENSITIVITY);
+    let mut cursor_captured = true;
+    renderer.set_cursor_captured(cursor_captured);
+    let mut pressed_keys = HashSet::new();
+    let mut frame_timer = FrameTimer::new();
+    let mut stats = String::new();
+    let mut last_stats_update = Instant::now();
+    let mut minimized = false;
+    // Set once the loop waited for events in reactive mode
+    let mut waited = false;
+    // Time not yet covered by updates, less than a step once they're done
+    let mut accumulator = 0.0;
 
     loop {
+        let frame_start = Instant::now();
+        let frame_time = frame_timer.tick();
+        // Time spent waiting doesn't move the camera
+        let delta_time = if waited { 0.0 } else { frame_time };
+
+        // A minimized window has no area to draw into, the swapchain can't be
+        // acquired from. Only its events are handled until it's restored.
+        if minimized {
+            thread::sleep(PAUSED_POLL_INTERVAL);
+        } else {
+            let fps = frame_timer.fps();
+            if fps > 0.0 && last_stats_update.elapsed() >= STATS_UPDATE_INTERVAL {
+                let frame_time = 1000.0 / fps;
+                let cpu_time = frame_timer.cpu_time().unwrap_or(0.0);
+                stats = format!("{:.0} FPS\n{:.2} ms\nCPU {:.2} ms", fps, frame_time, cpu_time);
+                if let Some(gpu_frame_time) = renderer.gpu_frame_time() {
+                    stats += &format!("\nGPU {:.2} ms", gpu_frame_time);
+                }
+                last_stats_update = Instant::now();
+            }
+            renderer.text().draw_text(&stats, STATS_POSITION.0, STATS_POSITION.1);
+
+            if config.show_axes {
+                add_axes(renderer.debug_lines());
+            }
+
+            camera.translate(&pressed_keys, delta_time);
+            renderer.set_camera(camera.position, camera.forward());
+
+            // Updating in steps of a fixed length keeps the simulation the same
+            // on fast and slow machines, each frame then shows it between the
+            // last two steps
+            accumulator += frame_time.min(MAX_FRAME_TIME);
+            while accumulator >= TIMESTEP {
+                renderer.update(TIMESTEP);
+                accumulator -= TIMESTEP;
+            }
+            let alpha = accumulator / TIMESTEP;
 
-        let (image_num, acquire_future) = swapchain::acquire_next_image(swapchain.clone(), None)?;
+            if let Some(wave_vertices) = &wave_vertices {
+                let time = renderer.interpolated_time(alpha);
+                renderer.update_vertices(&scene::make_wave(wave_vertices, time))?;
+            }
 
-        let command_buffer =
-            AutoCommandBufferBuilder::primary_one_time_submit(
-                device.clone(), queue.family()
-            )?
-                .begin_render_pass(framebuffers[image_num].clone(), false, vec![[0.0, 0.0, 1.0, 1.0].into()])?
-                .draw(pipeline.clone(), &dynamic_state, vertex_buffer.clone(), (), ())?
-                .end_render_pass()?
-                .build()?;
+            match renderer.render(alpha) {
+                Ok(()) => (),
+                // Recreating everything from scratch once, a second loss means
+                // the GPU can't keep up with the scene
+                Err(err) if err.requires_new_device() && !recovered_device_loss => {
+                    println!("Warning: {}, reinitializing the renderer", err);
+                    recovered_device_loss = true;
+
+                    // The window has to be released before a new surface is made for it
+                    renderer.abandon();
+                    renderer = Renderer::new(&config, &events_loop)?;
+                    renderer.set_cursor_captured(cursor_captured);
+                },
+                Err(err) => return Err(err),
+            }
 
-        let future = acquire_future
-            .then_execute(queue.clone(), command_buffer)?
-            .then_swapchain_present(queue.clone(), swapchain.clone(), image_num)
-            .then_signal_fence_and_flush();
+            // The CPU time covers the frame's work up to its submission, the
+            // GPU time is that of a previous frame, the latest one known
+            frame_timer.record_cpu_time(frame_start.elapsed());
+            if let Some(profiler) = &mut profiler {
+                profiler.record(
+                    frame_start.elapsed(), renderer.acquire_time(), renderer.record_time(),
+                    renderer.last_gpu_frame_time()
+                )?;
+            }
+        }
 
         let mut done = false;
-        events_loop.poll_events(|event| {
+        let mut resized = false;
+        let mut toggle_wireframe = false;
+        let mut toggle_culling = false;
+        let mut line_width_delta = 0.0;
+        let mut toggle_mask = false;
+        let mut reload_texture = false;
+        let mut toggle_spin = false;
+        let mut cycle_topology = false;
+        let mut toggle_fullscreen = false;
+        let mut toggle_cursor_capture = false;
+        let mut take_screenshot = false;
+        let mut mouse_delta = (0.0, 0.0);
+        // Held keys keep moving the camera, even without any event
+        waited = config.reactive && pressed_keys.is_empty();
+
+        // Returns whether the event changes what's drawn
+        let mut handle_event = |event: winit::Event| {
+            let redraw = match &event {
+                winit::Event::WindowEvent { .. } => true,
+                winit::Event::DeviceEvent { event: winit::DeviceEvent::MouseMotion { .. }, .. } =>
+                    cursor_captured,
+                _ => false,
+            };
 
             match event {
                 winit::Event::WindowEvent { event: winit::WindowEvent::CloseRequested, .. } => {
                     done = true;
                 },
+                winit::Event::WindowEvent { event: winit::WindowEvent::Resized(size), .. } => {
+                    minimized = size.width == 0.0 || size.height == 0.0;
+                    resized = true;
+                },
+                // Moving to a monitor of another DPI keeps the logical size but
+                // changes how many physical pixels it covers. Without a new
+                // swapchain the old one would be stretched over them, blurring it.
+                winit::Event::WindowEvent { event: winit::WindowEvent::HiDpiFactorChanged(_), .. } => {
+                    resized = true;
+                },
+                winit::Event::WindowEvent { event: winit::WindowEvent::Focused(false), .. } => {
+                    // Keys released while another window has the focus are never
+                    // reported, the camera would keep moving
+                    pressed_keys.clear();
+                },
+                winit::Event::WindowEvent {
+                    event: winit::WindowEvent::KeyboardInput {
+                        input: KeyboardInput { state, virtual_keycode: Some(key), modifiers, .. },
+                        ..
+                    },
+                    ..
+                } => {
+                    match state {
+                        ElementState::Pressed => {
+                            // Key repeats don't count, holding a toggle key doesn't flicker
+                            if pressed_keys.insert(key) {
+                                match key {
+                                    VirtualKeyCode::F => toggle_wireframe = true,
+                                    VirtualKeyCode::C => toggle_culling = true,
+                                    VirtualKeyCode::RBracket => line_width_delta += LINE_WIDTH_STEP,
+                                    VirtualKeyCode::LBracket => line_width_delta -= LINE_WIDTH_STEP,
+                                    VirtualKeyCode::M => toggle_mask = true,
+                                    VirtualKeyCode::R => reload_texture = true,
+                                    VirtualKeyCode::T => toggle_spin = true,
+                                    VirtualKeyCode::P => cycle_topology = true,
+                                    VirtualKeyCode::Return if modifiers.alt => toggle_fullscreen = true,
+                                    VirtualKeyCode::Tab => toggle_cursor_capture = true,
+                                    VirtualKeyCode::F12 => take_screenshot = true,
+                                    _ => (),
+                                }
+                            }
+                        },
+                        ElementState::Released => { pressed_keys.remove(&key); },
+                    }
+
+                    if key == VirtualKeyCode::Escape {
+                        done = true;
+                    }
+                },
+                // Raw motion keeps coming once the cursor hits the window edge
+                winit::Event::DeviceEvent {
+                    event: winit::DeviceEvent::MouseMotion { delta: (dx, dy) }, ..
+                } => {
+                    mouse_delta.0 += dx;
+                    mouse_delta.1 += dy;
+                },
                 _ => (),
             }
-        });
-        if done { break; }
-    }
 
-    Ok(())
-}
-
-fn init() ->
-    Result<
-        (
-            Arc<Instance>, Arc<Device>, Arc<Queue>,
-            Arc<Surface<Window>>, Capabilities, EventsLoop
-        ),
-        Box<Error>
-    >
-{
-    let instance = {
-        let extensions = vulkano_win::required_extensions();
-        Instance::new(None, &extensions, None)?
-    };
+            redraw
+        };
 
-    #[cfg(debug_assertions)]
-    {
-        println!("Listing available devices supporting Vulkan API: ");
-        for device in PhysicalDevice::enumerate(&instance) {
-            println!("{:?}: {:?}", device.name(), device);
-
-            print!("Device contains queue families with this queue(s) amount: ");
-            for family in device.queue_families() {
-                print!("{:?} ", family.queues_count());
+        // Reactive mode sleeps until an event needs the window redrawn, such as
+        // input, a resize or a `Refresh` when the window manager lost its
+        // content, then draws a single frame for it. It saves power, at the cost
+        // of the animations only advancing by jumps, and shader reloads only
+        // showing on the next event. winit 0.19 has no way of asking for a
+        // redraw from within the loop, that came with `request_redraw` in 0.20.
+        if waited {
+            events_loop.run_forever(|event|
+                if handle_event(event) { ControlFlow::Break } else { ControlFlow::Continue });
+        }
+        // Whatever else already came in is handled along with it
+        events_loop.poll_events(|event| { handle_event(event); });
+        if done { break; }
+        // Restoring the window resizes it back, recreating the swapchain
+        if resized { renderer.handle_display_change(&events_loop); }
+        if resized && !minimized { renderer.recreate_swapchain()?; }
+        if toggle_wireframe { renderer.toggle_wireframe(); }
+        if toggle_culling {
+            let faces = if renderer.toggle_culling() { "front faces only" } else { "both faces" };
+            println!("Drawing the {}", faces);
+        }
+        if line_width_delta != 0.0 {
+            println!("Drawing lines {} pixels wide", renderer.change_line_width(line_width_delta));
+        }
+        if toggle_mask { renderer.toggle_mask(); }
+        if reload_texture { renderer.reload_texture()?; }
+        if toggle_spin {
+            let side = if renderer.toggle_spin_on_cpu() { "CPU" } else { "GPU" };
+            println!("Spinning the geometry on the {}", side);
+        }
+        if cycle_topology { println!("Drawing the geometry as {}", renderer.cycle_topology().name()); }
+        if toggle_fullscreen { renderer.toggle_fullscreen(events_loop.get_primary_monitor()); }
+        if take_screenshot { renderer.request_screenshot()?; }
+
+        #[cfg(debug_assertions)]
+        {
+            if shader_watcher.as_ref().is_some_and(|watcher| watcher.changed()) {
+                if let Err(err) = renderer.reload_shaders() {
+                    // Reported last, compile errors span several lines
+                    println!("Warning: keeping the previous shaders, {}", err);
+                }
+            }
+        }
+        if toggle_cursor_capture {
+            cursor_captured = !cursor_captured;
+            renderer.set_cursor_captured(cursor_captured);
+        }
+        if cursor_captured { camera.rotate(mouse_delta.0, mouse_delta.1); }
+
+        // Only sleeps for what's left of the frame once its work is done. Resize
+        // frames are already slowed down by the swapchain recreation. Without
+        // `--max-fps`, a vsynced present already waits for the display.
+        let min_frame_duration =
+            max_fps_duration.or(if renderer.is_vsynced() { None } else { default_frame_duration });
+        if let Some(min_frame_duration) = min_frame_duration {
+            let frame_duration = frame_start.elapsed();
+            if !resized && frame_duration < min_frame_duration {
+                thread::sleep(min_frame_duration - frame_duration);
             }
-
-            println!("---");
         }
-
-        println!("");
-    }
-
-    let chosen_physical_device =
-        PhysicalDevice::enumerate(&instance).nth(0)
-            .expect("Error: NoneError: No physical devices supporting Vulkan API found");
-
-    #[cfg(debug_assertions)]
-    {
-        println!(
-            "Chosen device: {:?}: {:?}",
-            chosen_physical_device.name(),
-            chosen_physical_device);
-
-        println!("");
     }
 
-    let chosen_family = chosen_physical_device.queue_families()
-        .find(|&q| q.supports_graphics())
-        .expect("Error: NoneError: No family supporting GRAPHICS_BIT found in chosen device");
-
-    let (chosen_logical_device, mut queues) = {
-        let mut chosen_extensions = DeviceExtensions::none();
-        // // "khr_storage_buffer_storage_class" is required in vulkano="0.16.0"
-        // chosen_extensions.khr_storage_buffer_storage_class = true;
-        chosen_extensions.khr_swapchain = true;
-
-        Device::new(
-            chosen_physical_device,
-            chosen_physical_device.supported_features(),
-            &chosen_extensions,
-            [(chosen_family, 0.5)].iter().cloned()
-        )?
-    };
-
-    let chosen_queue = queues.next()
-        .expect("Error: NoneError: No queue found in chosen family");
-
-
-    let mut events_loop = EventsLoop::new();
-    let surface =
-        WindowBuilder::new().build_vk_surface(
-            &events_loop, instance.clone()
-        )?;
-
-    let capabilities = surface.capabilities(chosen_physical_device)?;
-
-    Ok((
-        instance, chosen_logical_device, chosen_queue,
-        surface, capabilities, events_loop
-    ))
+    Ok(())
 }
 
-/// This method is called once during initialization, then again whenever the window is resized
-fn window_size_dependent_setup(
-    images: &[Arc<SwapchainImage<Window>>],
-    render_pass: Arc<dyn RenderPassAbstract + Send + Sync>,
-    dynamic_state: &mut DynamicState
-) -> Vec<Arc<dyn FramebufferAbstract + Send + Sync>> {
-    let dimensions = images[0].dimensions();
-
-    let viewport = Viewport {
-        origin: [0.0, 0.0],
-        dimensions: [dimensions[0] as f32, dimensions[1] as f32],
-        depth_range: 0.0 .. 1.0,
-    };
-    dynamic_state.viewports = Some(vec!(viewport));
-
-    images.iter().map(|image| {
-        Arc::new(
-            Framebuffer::start(render_pass.clone())
-                .add(image.clone()).unwrap()
-                .build().unwrap()
-        ) as Arc<dyn FramebufferAbstract + Send + Sync>
-    }).collect::<Vec<_>>()
+/// Adds the world axes, X red, Y green and Z blue, along with the cube going
+/// from -1 to 1 on each of them
+fn add_axes(debug_lines: &mut DebugLines) {
+    debug_lines.add_line([0.0; 3], [1.0, 0.0, 0.0], [1.0, 0.0, 0.0]);
+    debug_lines.add_line([0.0; 3], [0.0, 1.0, 0.0], [0.0, 1.0, 0.0]);
+    debug_lines.add_line([0.0; 3], [0.0, 0.0, 1.0], [0.0, 0.0, 1.0]);
+    debug_lines.add_aabb([-1.0; 3], [1.0; 3], [1.0, 1.0, 0.0]);
 }
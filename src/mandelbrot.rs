@@ -0,0 +1,177 @@
+// Build-in modules
+use std::sync::Arc;
+
+// External modules
+use image::{ImageBuffer, Rgba};
+use vulkano::buffer::BufferUsage;
+use vulkano::buffer::CpuAccessibleBuffer;
+use vulkano::command_buffer::AutoCommandBufferBuilder;
+use vulkano::command_buffer::CommandBuffer;
+use vulkano::descriptor::descriptor_set::PersistentDescriptorSet;
+use vulkano::device::Device;
+use vulkano::device::DeviceExtensions;
+use vulkano::device::Features;
+use vulkano::format::Format;
+use vulkano::image::Dimensions;
+use vulkano::image::StorageImage;
+use vulkano::instance::InstanceExtensions;
+use vulkano::pipeline::ComputePipeline;
+use vulkano::sync::GpuFuture;
+
+// Internal modules
+use crate::config::Config;
+use crate::error::AppError;
+use crate::renderer;
+
+const WIDTH: u32 = 1024;
+const HEIGHT: u32 = 1024;
+
+/// Where the rendered image is written to
+const OUTPUT_PATH: &str = "mandelbrot.png";
+
+/// Pixels `--verify` checks: away from the edges of the set, where rounding
+/// differences between the GPU and CPU barely change the iteration count, both
+/// inside it and at several distances outside
+const VERIFIED_PIXELS: [[u32; 2]; 9] =
+    [[0, 0], [100, 900], [200, 300], [512, 850], [700, 150], [640, 700], [880, 880], [512, 512], [768, 512]];
+
+/// How far apart the iteration counts of the GPU and CPU may be for a pixel
+const ITERATION_TOLERANCE: u32 = 2;
+
+/// What the shader adds to its iteration variable on each iteration, which it
+/// writes out once done
+const ITERATION_STEP: f32 = 0.005;
+
+mod cs {
+    vulkano_shaders::shader!{
+        ty: "compute",
+        path: "src/shaders/mandelbrot.comp"
+    }
+}
+
+/// Renders the Mandelbrot set with a compute shader and saves it as a PNG,
+/// without any window or swapchain, on the device `config` prefers. With
+/// `verify`, also checks some of the pixels against the CPU.
+pub fn run(config: &Config, verify: bool) -> Result<(), AppError> {
+    let instance = renderer::create_instance(InstanceExtensions::none())?;
+    let physical_device = renderer::choose_physical_device(&instance, config)?;
+
+    let family = physical_device.queue_families()
+        .find(|&q| q.supports_compute())
+        .ok_or_else(|| AppError::NoSuitableDevice(
+            "no family supporting COMPUTE_BIT found in chosen device".into()
+        ))?;
+
+    let (device, mut queues) =
+        Device::new(
+            physical_device,
+            &Features::none(),
+            &DeviceExtensions::none(),
+            [(family, 0.5)].iter().cloned()
+        )?;
+
+    let queue = queues.next()
+        .ok_or_else(|| AppError::NoSuitableDevice("no queue found in chosen family".into()))?;
+
+    let image =
+        StorageImage::new(
+            device.clone(),
+            Dimensions::Dim2d { width: WIDTH, height: HEIGHT },
+            Format::R8G8B8A8Unorm,
+            Some(queue.family())
+        )?;
+
+    let shader = cs::Shader::load(device.clone())?;
+    let pipeline =
+        Arc::new(ComputePipeline::new(device.clone(), &shader.main_entry_point(), &())?);
+
+    let set =
+        Arc::new(
+            PersistentDescriptorSet::start(pipeline.clone(), 0)
+                .add_image(image.clone())?
+                .build()?
+        );
+
+    let buffer =
+        CpuAccessibleBuffer::from_iter(
+            device.clone(), BufferUsage::all(),
+            (0 .. WIDTH * HEIGHT * 4).map(|_| 0u8)
+        )?;
+
+    // The shader works on groups of 8x8 pixels
+    let command_buffer =
+        AutoCommandBufferBuilder::new(device.clone(), queue.family())?
+            .dispatch([WIDTH / 8, HEIGHT / 8, 1], pipeline.clone(), set, ())?
+            .copy_image_to_buffer(image.clone(), buffer.clone())?
+            .build()?;
+
+    command_buffer.execute(queue.clone())?
+        .then_signal_fence_and_flush()?
+        .wait(None)?;
+
+    let buffer_content = buffer.read()?;
+    let image = ImageBuffer::<Rgba<u8>, _>::from_raw(WIDTH, HEIGHT, &buffer_content[..])
+        .ok_or(AppError::InvalidImageData("buffer is too small for the image"))?;
+
+    image.save(OUTPUT_PATH)?;
+    println!("Saved {}", OUTPUT_PATH);
+
+    if verify {
+        verify_pixels(&image)?;
+    }
+
+    Ok(())
+}
+
+/// Compares the iteration counts of the `VERIFIED_PIXELS` of `image` with the
+/// CPU's, printing whether each matches
+fn verify_pixels(image: &ImageBuffer<Rgba<u8>, &[u8]>) -> Result<(), AppError> {
+    let mut failures = 0;
+
+    for &[x, y] in VERIFIED_PIXELS.iter() {
+        // The shader writes its iteration variable in every color channel
+        let value = image.get_pixel(x, y)[0] as f32 / 255.0;
+        let gpu_iterations = (value / ITERATION_STEP).round() as u32;
+        let cpu_iterations = (reference_value(x, y) / ITERATION_STEP).round() as u32;
+
+        let passed = gpu_iterations.abs_diff(cpu_iterations) <= ITERATION_TOLERANCE;
+        if !passed {
+            failures += 1;
+        }
+
+        println!(
+            "{} ({}, {}): {} iterations, expected {}",
+            if passed { "PASS" } else { "FAIL" }, x, y, gpu_iterations, cpu_iterations
+        );
+    }
+
+    if failures > 0 {
+        return Err(AppError::VerificationFailed(failures));
+    }
+
+    Ok(())
+}
+
+/// The value the shader writes at pixel (`x`, `y`), computed the same way in
+/// single precision
+fn reference_value(x: u32, y: u32) -> f32 {
+    let norm_x = (x as f32 + 0.5) / WIDTH as f32;
+    let norm_y = (y as f32 + 0.5) / HEIGHT as f32;
+    let (c_x, c_y) = ((norm_x - 0.5) * 2.0 - 1.0, (norm_y - 0.5) * 2.0);
+
+    let (mut z_x, mut z_y) = (0.0f32, 0.0f32);
+    let mut i = 0.0;
+    while i < 1.0 {
+        let next_x = z_x * z_x - z_y * z_y + c_x;
+        z_y = z_y * z_x + z_x * z_y + c_y;
+        z_x = next_x;
+
+        if (z_x * z_x + z_y * z_y).sqrt() > 4.0 {
+            break;
+        }
+        i += ITERATION_STEP;
+    }
+
+    // Stored in a normalized image, which clamps it
+    i.min(1.0)
+}
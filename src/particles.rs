@@ -0,0 +1,191 @@
+// Build-in modules
+use std::cell::Cell;
+use std::sync::Arc;
+
+// External modules
+use vulkano::buffer::BufferUsage;
+use vulkano::buffer::DeviceLocalBuffer;
+use vulkano::buffer::TypedBufferAccess;
+use vulkano::command_buffer::AutoCommandBufferBuilder;
+use vulkano::command_buffer::DynamicState;
+use vulkano::descriptor::DescriptorSet;
+use vulkano::descriptor::descriptor_set::PersistentDescriptorSet;
+use vulkano::descriptor::pipeline_layout::PipelineLayout;
+use vulkano::device::{Device, Queue};
+use vulkano::framebuffer::RenderPassAbstract;
+use vulkano::framebuffer::Subpass;
+use vulkano::pipeline::ComputePipeline;
+use vulkano::pipeline::GraphicsPipeline;
+use vulkano::pipeline::GraphicsPipelineAbstract;
+use vulkano::pipeline::vertex::SingleBufferDefinition;
+
+// Internal modules
+use crate::error::AppError;
+use crate::scene;
+
+/// Particles each invocation of the compute shader moves, as in the shader
+const GROUP_SIZE: u32 = 64;
+
+/// Longest step the particles are moved by at once, in seconds. Past that, as
+/// after the window was minimized, they'd go through the edges.
+const MAX_STEP: f32 = 0.1;
+
+/// Laid out as declared, since the compute shader reads it as a std430
+/// struct
+#[derive(Default, Copy, Clone)]
+#[repr(C)]
+struct Particle {
+    /// In clip space, within [-1, 1] along both axes
+    pos: [f32; 2],
+    /// In clip space units per second
+    vel: [f32; 2],
+}
+vulkano::impl_vertex!(Particle, pos, vel);
+
+mod cs {
+    vulkano_shaders::shader!{
+        ty: "compute",
+        path: "src/shaders/particles.comp"
+    }
+}
+
+mod vs {
+    vulkano_shaders::shader!{
+        ty: "vertex",
+        path: "src/shaders/particles.vert"
+    }
+}
+
+mod fs {
+    vulkano_shaders::shader!{
+        ty: "fragment",
+        path: "src/shaders/particles.frag"
+    }
+}
+
+/// Points falling and bouncing off the edges of the scene, moved on the GPU
+/// by a compute shader then drawn straight from the same buffer
+pub struct Particles {
+    update_pipeline: Arc<ComputePipeline<PipelineLayout<cs::Layout>>>,
+    draw_pipeline: Arc<dyn GraphicsPipelineAbstract + Send + Sync>,
+    particles: Arc<DeviceLocalBuffer<[Particle]>>,
+    update_set: Arc<dyn DescriptorSet + Send + Sync>,
+    /// Steps the particles are yet to be moved by, in seconds, all recorded
+    /// with the next frame
+    pending_steps: Cell<Vec<f32>>,
+}
+
+impl Particles {
+    /// Scatters `count` particles across the scene, uploaded through `queue`,
+    /// to be drawn in the subpass `subpass` of `render_pass`
+    pub fn new(
+        device: Arc<Device>,
+        queue: &Arc<Queue>,
+        render_pass: &Arc<dyn RenderPassAbstract + Send + Sync>,
+        subpass: u32,
+        count: u32
+    ) -> Result<Self, AppError> {
+        let cs = cs::Shader::load(device.clone())?;
+        let update_pipeline =
+            Arc::new(ComputePipeline::new(device.clone(), &cs.main_entry_point(), &())?);
+
+        let vs = vs::Shader::load(device.clone())?;
+        let fs = fs::Shader::load(device.clone())?;
+
+        // Without any depth test, they show through the geometry
+        let draw_pipeline =
+            Arc::new(
+                GraphicsPipeline::start()
+                    .vertex_input(SingleBufferDefinition::<Particle>::new())
+                    .vertex_shader(vs.main_entry_point(), ())
+                    .point_list()
+                    .viewports_scissors_dynamic(1)
+                    .fragment_shader(fs.main_entry_point(), ())
+                    .render_pass(
+                        Subpass::from(render_pass.clone(), subpass)
+                            .ok_or(AppError::MissingSubpass(subpass))?
+                    )
+                    .build(device.clone())?
+            );
+
+        let particles =
+            scene::upload_to_device_local(
+                &device, queue, &scatter(count),
+                BufferUsage { storage_buffer: true, vertex_buffer: true, .. BufferUsage::none() }
+            )?;
+
+        let update_set =
+            Arc::new(
+                PersistentDescriptorSet::start(update_pipeline.clone(), 0)
+                    .add_buffer(particles.clone())?
+                    .build()?
+            );
+
+        println!("Simulating {} particles", count);
+
+        Ok(Particles {
+            update_pipeline,
+            draw_pipeline,
+            particles,
+            update_set,
+            pending_steps: Cell::new(Vec::new()),
+        })
+    }
+
+    /// Moves the particles `delta_time` seconds further once the next frame is
+    /// drawn. Steps of a fixed length keep the simulation the same whatever
+    /// the frame rate.
+    pub fn advance(&self, delta_time: f32) {
+        let mut pending_steps = self.pending_steps.take();
+        pending_steps.push(delta_time.clamp(0.0, MAX_STEP));
+        self.pending_steps.set(pending_steps);
+    }
+
+    /// Records moving the particles by the steps `advance` was called with
+    /// since the last frame, one dispatch each. Must be recorded outside of
+    /// any render pass.
+    ///
+    /// Each dispatch needs a barrier from the writes of the one before, and
+    /// the draw reading them as vertices afterwards one from the last, which
+    /// vulkano inserts itself as it does for the GPU wave.
+    pub fn update(&self, mut builder: AutoCommandBufferBuilder)
+        -> Result<AutoCommandBufferBuilder, AppError>
+    {
+        let group_count = (self.particles.len() as u32).div_ceil(GROUP_SIZE);
+
+        for delta_time in self.pending_steps.take() {
+            builder =
+                builder.dispatch(
+                    [group_count, 1, 1], self.update_pipeline.clone(), self.update_set.clone(),
+                    cs::ty::PushConstants { delta_time }
+                )?;
+        }
+
+        Ok(builder)
+    }
+
+    /// Records the draw of the particles as points, within the subpass they
+    /// were prepared for
+    pub fn draw(&self, builder: AutoCommandBufferBuilder, dynamic_state: &DynamicState)
+        -> Result<AutoCommandBufferBuilder, AppError>
+    {
+        Ok(builder.draw(
+            self.draw_pipeline.clone(), dynamic_state, vec![self.particles.clone()], (), ()
+        )?)
+    }
+}
+
+/// Spreads `count` particles evenly over the scene, each thrown in its own
+/// direction. Offsetting every one by irrational fractions of the scene,
+/// rather than at random, covers it without any clumps.
+fn scatter(count: u32) -> Vec<Particle> {
+    // In double precision, the fractions of large products would be lost
+    let spread = |index: u32, step: f64| ((index as f64 * step).fract() * 2.0 - 1.0) as f32;
+
+    (0 .. count)
+        .map(|index| Particle {
+            pos: [spread(index, 0.618_034), spread(index, 0.754_878)],
+            vel: [0.5 * spread(index, 0.569_840), 0.5 * spread(index, 0.438_744)],
+        })
+        .collect()
+}
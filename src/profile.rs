@@ -0,0 +1,75 @@
+// Build-in modules
+use std::fs::File;
+use std::io::{BufWriter, Write};
+use std::time::Duration;
+
+// Internal modules
+use crate::error::AppError;
+
+/// Writes the timings of every frame as a row of a CSV file, to plot them
+/// afterwards. Rows are buffered and flushed when dropped, however the loop
+/// writing them ends, and the average recording time is printed then, to
+/// compare runs with different `--record-threads`.
+pub struct Profiler {
+    writer: BufWriter<File>,
+    frame_index: u64,
+    /// Threads recording the instances, 1 without `--record-threads`
+    record_threads: u32,
+    /// Total of the `record_time` of the rows written
+    total_record_time: Duration,
+}
+
+impl Profiler {
+    /// Creates the file at `path`, replacing any previous one, and writes
+    /// the header row. `record_threads` is only reported along with the
+    /// average recording time.
+    pub fn create(path: &str, record_threads: u32) -> Result<Self, AppError> {
+        let mut writer = BufWriter::new(File::create(path)?);
+        writeln!(writer, "frame,cpu_ms,acquire_ms,record_ms,gpu_ms")?;
+
+        Ok(Profiler { writer, frame_index: 0, record_threads, total_record_time: Duration::ZERO })
+    }
+
+    /// Appends the row of the next frame, which took `cpu_time` on the CPU,
+    /// `acquire_time` of it acquiring the swapchain image and `record_time`
+    /// recording its command buffers. `gpu_time` is left empty when the GPU
+    /// time isn't measured.
+    pub fn record(
+        &mut self,
+        cpu_time: Duration,
+        acquire_time: Duration,
+        record_time: Duration,
+        gpu_time: Option<f32>
+    ) -> Result<(), AppError> {
+        write!(
+            self.writer, "{},{:.3},{:.3},{:.3},",
+            self.frame_index, cpu_time.as_secs_f64() * 1000.0, acquire_time.as_secs_f64() * 1000.0,
+            record_time.as_secs_f64() * 1000.0
+        )?;
+        match gpu_time {
+            Some(gpu_time) => writeln!(self.writer, "{:.3}", gpu_time)?,
+            None => writeln!(self.writer)?,
+        }
+
+        self.frame_index += 1;
+        self.total_record_time += record_time;
+        Ok(())
+    }
+}
+
+impl Drop for Profiler {
+    // `BufWriter` flushes itself when dropped too, but silently drops any
+    // error doing so
+    fn drop(&mut self) {
+        if let Err(err) = self.writer.flush() {
+            println!("Warning: could not write the profile: {}", err);
+        }
+
+        if self.frame_index > 0 {
+            let average_record_time = self.total_record_time.as_secs_f64() * 1000.0 / self.frame_index as f64;
+            println!(
+                "Recording took {:.3} ms per frame on average over {} frames, on {} thread(s)",
+                average_record_time, self.frame_index, self.record_threads);
+        }
+    }
+}
@@ -0,0 +1,1308 @@
+// Build-in modules
+use std::io::{self, BufRead, Write};
+use std::mem;
+use std::sync::Arc;
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+
+// External modules
+use image::{ImageBuffer, Rgba};
+use vulkano::buffer::BufferUsage;
+use vulkano::buffer::CpuAccessibleBuffer;
+use vulkano::command_buffer::AutoCommandBufferBuilder;
+use vulkano::device::Device;
+use vulkano::device::DeviceExtensions;
+use vulkano::device::Features;
+use vulkano::device::Queue;
+use vulkano::format::Format;
+#[cfg(debug_assertions)]
+use vulkano::instance::debug::{DebugCallback, MessageTypes};
+use vulkano::image::ImageUsage;
+use vulkano::image::swapchain::SwapchainImage;
+use vulkano::instance::Instance;
+use vulkano::instance::InstanceExtensions;
+use vulkano::instance::PhysicalDevice;
+use vulkano::instance::PhysicalDeviceType;
+use vulkano::instance;
+use vulkano::OomError;
+use vulkano::swapchain::AcquireError;
+use vulkano::swapchain::Capabilities;
+use vulkano::swapchain::ColorSpace;
+use vulkano::swapchain::Surface;
+use vulkano::swapchain::SwapchainCreationError;
+use vulkano::swapchain::{Swapchain, SurfaceTransform, PresentMode};
+use vulkano::swapchain;
+use vulkano::sync::FenceSignalFuture;
+use vulkano::sync::FlushError;
+use vulkano::sync::GpuFuture;
+use vulkano::sync;
+use vulkano_win::VkSurfaceBuild;
+use winit::EventsLoop;
+use winit::MonitorId;
+use winit::Window;
+use winit::WindowBuilder;
+use winit::dpi::LogicalSize;
+
+// Internal modules
+use crate::config::{Config, DevicePreference, Mode, SwapchainEncoding};
+use crate::debug_lines::DebugLines;
+use crate::error::AppError;
+use crate::gpu_timer::GpuTimer;
+use crate::histogram;
+use crate::scene::Scene;
+use crate::scene::Targets;
+use crate::scene::Topology;
+use crate::scene::Vertex;
+use crate::text::TextRenderer;
+
+/// Khronos' standard validation layer, checking the API usage in debug builds
+const VALIDATION_LAYER: &str = "VK_LAYER_KHRONOS_validation";
+
+/// Priorities of the graphics queue and of the upload queue, relative to each
+/// other
+const RENDER_QUEUE_PRIORITY: f32 = 1.0;
+const UPLOAD_QUEUE_PRIORITY: f32 = 0.25;
+
+/// How often the luminance histogram is printed, when enabled
+const HISTOGRAM_PRINT_INTERVAL: Duration = Duration::from_secs(1);
+
+/// Signalled once the GPU is done with a submitted frame
+pub type FrameFence = Arc<FenceSignalFuture<Box<dyn GpuFuture + Send + Sync>>>;
+
+/// The device with its graphics queue, then the queue used for uploads
+type DeviceAndQueues = (Arc<Device>, Arc<Queue>, Arc<Queue>);
+
+/// A swapchain along with its images
+type SwapchainAndImages = (Arc<Swapchain<Window>>, Vec<Arc<SwapchainImage<Window>>>);
+
+/// Order of the channels of an image's pixels in memory
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum ChannelOrder {
+    /// One byte each, red first like PNG files
+    Rgba,
+    /// One byte each, blue first, which many swapchains prefer
+    Bgra,
+    /// Not four bytes per pixel, or not a color format
+    Other,
+}
+
+/// What the swapchain images are used for, beyond being presented
+#[derive(Clone, Copy)]
+pub struct SwapchainUsage {
+    /// Creating the swapchain fails unless the surface supports all of it
+    pub needed: ImageUsage,
+    /// Only given to the images as far as the surface supports it
+    pub optional: ImageUsage,
+}
+
+impl SwapchainUsage {
+    /// Drawing into the images, optionally copying them out for screenshots
+    pub fn draw() -> Self {
+        SwapchainUsage {
+            needed: ImageUsage { color_attachment: true, .. ImageUsage::none() },
+            optional: ImageUsage { transfer_source: true, .. ImageUsage::none() },
+        }
+    }
+}
+
+/// Owns every Vulkan object needed to draw the scene into the window
+pub struct Renderer {
+    _context: VulkanContext,
+    device: Arc<Device>,
+    queue: Arc<Queue>,
+    surface: Arc<Surface<Window>>,
+
+    swapchain: Arc<Swapchain<Window>>,
+    images: Vec<Arc<SwapchainImage<Window>>>,
+    scene: Scene,
+    targets: Targets,
+
+    // Frames in flight: one fence slot per swapchain image. The slot holds the
+    // fence of the last frame rendered into that image, so the CPU only blocks
+    // when it's about to reuse an image the GPU is still working on, and can
+    // record frame N+1 meanwhile the GPU renders frame N.
+    fences: Vec<Option<FrameFence>>,
+    previous_fence_index: usize,
+    /// Fence of the last frame drawn from each of the scene's vertex buffers
+    vertex_buffer_fences: [Option<FrameFence>; 2],
+
+    /// Set when the swapchain no longer matches the surface, it is then
+    /// recreated at the beginning of the next frame
+    swapchain_outdated: bool,
+    /// Size of the window before switching to fullscreen, `None` when windowed
+    windowed_size: Option<LogicalSize>,
+    /// Monitor `--monitor` picked, which fullscreen always fills with a
+    /// swapchain of its exact resolution
+    exclusive_monitor: Option<MonitorId>,
+    /// Set when the next frame presented should also be saved as a PNG
+    screenshot_requested: bool,
+    /// Seconds the animation and simulation were advanced by `update`, and
+    /// the length of the last step, which frames are interpolated within
+    time: f32,
+    last_step: f32,
+    /// `None` when the device can't measure the GPU time
+    gpu_timer: Option<GpuTimer>,
+    /// How long the last frame waited for a swapchain image
+    acquire_time: Duration,
+    /// How long recording the last frame's command buffers took
+    record_time: Duration,
+    last_histogram_print: Instant,
+}
+
+impl Renderer {
+    /// Draws the scene `config` describes into a new window of `events_loop`
+    pub fn new(config: &Config, events_loop: &EventsLoop) -> Result<Self, AppError> {
+        let context = VulkanContext::new(vulkano_win::required_extensions())?;
+
+        #[cfg(debug_assertions)]
+        {
+            println!("Listing available devices supporting Vulkan API: ");
+            for device in PhysicalDevice::enumerate(&context.instance) {
+                println!("{}: {:?}: {:?}", device.index(), device.name(), device);
+
+                print!("Device contains queue families with this queue(s) amount: ");
+                for family in device.queue_families() {
+                    print!("{:?} ", family.queues_count());
+                }
+
+                println!("---");
+            }
+
+            println!();
+        }
+
+        let (device, queue, transfer_queue) = {
+            let mut chosen_extensions = DeviceExtensions::none();
+            // // "khr_storage_buffer_storage_class" is required in vulkano="0.16.0"
+            // chosen_extensions.khr_storage_buffer_storage_class = true;
+            // vulkano 0.13 doesn't know that extension, and doesn't need it: the
+            // shaders are compiled to SPIR-V 1.0, where storage buffers such as
+            // the wave compute shader's are declared without that storage class
+            chosen_extensions.khr_swapchain = true;
+
+            context.open_scene_device(config, &chosen_extensions)?
+        };
+
+        let exclusive_monitor = match config.monitor {
+            Some(index) => Some(choose_monitor(events_loop, index)?),
+            None => None,
+        };
+
+        let surface =
+            WindowBuilder::new()
+                .with_dimensions((config.width, config.height).into())
+                .with_title(config.title.clone())
+                .with_fullscreen(exclusive_monitor.clone())
+                .build_vk_surface(events_loop, context.instance.clone())?;
+
+        let format =
+            choose_swapchain_format(&surface.capabilities(device.physical_device())?, config.swapchain_encoding);
+        let (swapchain, images) =
+            create_swapchain(
+                &device, &queue, surface.clone(), format, config.present_mode, config.image_count,
+                SwapchainUsage::draw()
+            )?;
+
+        let scene =
+            Scene::from_config(device.clone(), queue.clone(), transfer_queue, swapchain.format(), config)?;
+        let targets = scene.create_targets(&images)?;
+
+        let fences = vec![None; images.len()];
+        let gpu_timer = GpuTimer::new(device.clone(), queue.clone())?;
+
+        Ok(Renderer {
+            _context: context,
+            device,
+            queue,
+            surface,
+
+            swapchain,
+            images,
+            scene,
+            targets,
+
+            fences,
+            previous_fence_index: 0,
+            vertex_buffer_fences: [None, None],
+
+            // The window may only get the monitor's size once it's mapped,
+            // the first frame checks the swapchain has it
+            swapchain_outdated: exclusive_monitor.is_some(),
+            windowed_size: exclusive_monitor.as_ref().map(|_| (config.width, config.height).into()),
+            exclusive_monitor,
+            screenshot_requested: false,
+            time: 0.0,
+            last_step: 0.0,
+            gpu_timer,
+            acquire_time: Duration::ZERO,
+            record_time: Duration::ZERO,
+            last_histogram_print: Instant::now(),
+        })
+    }
+
+    /// Recreates the swapchain and everything depending on its size. If the
+    /// window is in the middle of a resize, this is retried on the next frame.
+    pub fn recreate_swapchain(&mut self) -> Result<(), AppError> {
+        self.swapchain_outdated = true;
+
+        let capabilities = self.surface.capabilities(self.device.physical_device())?;
+        let dimensions =
+            match &self.exclusive_monitor {
+                Some(monitor) if self.windowed_size.is_some() => monitor_dimensions(monitor, &capabilities),
+                _ => get_dimensions(&self.surface, &capabilities),
+            };
+
+        let (new_swapchain, new_images) =
+            match self.swapchain.recreate_with_dimension(dimensions) {
+                Ok(r) => r,
+                // Happens while the user is resizing the window, try again next frame
+                Err(SwapchainCreationError::UnsupportedDimensions) => return Ok(()),
+                Err(err) => return Err(err.into()),
+            };
+
+        self.swapchain = new_swapchain;
+        self.images = new_images;
+        self.fences.resize(self.images.len(), None);
+        self.previous_fence_index = self.previous_fence_index.min(self.images.len() - 1);
+        self.targets = self.scene.create_targets(&self.images)?;
+
+        self.swapchain_outdated = false;
+
+        Ok(())
+    }
+
+    /// Releases the renderer of a lost device. Dropping the fences of the frames
+    /// in flight waits for them, which panics once the device is lost, so they
+    /// are leaked instead.
+    pub fn abandon(mut self) {
+        let vertex_buffer_fences = mem::take(&mut self.vertex_buffer_fences);
+        for fence in self.fences.drain(..).chain(vertex_buffer_fences).flatten() {
+            mem::forget(fence);
+        }
+        self.targets.forget_frames();
+    }
+
+    /// Average time the GPU took to run the last frames, in milliseconds, if
+    /// it can be measured
+    pub fn gpu_frame_time(&self) -> Option<f32> {
+        self.gpu_timer.as_ref().and_then(|gpu_timer| gpu_timer.average_frame_time())
+    }
+
+    /// Time the GPU took to run the last measured frame, in milliseconds, if
+    /// it can be measured
+    pub fn last_gpu_frame_time(&self) -> Option<f32> {
+        self.gpu_timer.as_ref().and_then(|gpu_timer| gpu_timer.last_frame_time())
+    }
+
+    /// How long the last frame drawn waited for a swapchain image
+    pub fn acquire_time(&self) -> Duration {
+        self.acquire_time
+    }
+
+    /// How long the last frame drawn took to record its command buffers, on
+    /// however many threads
+    pub fn record_time(&self) -> Duration {
+        self.record_time
+    }
+
+    /// Whether presenting waits for the display to refresh, which limits the
+    /// frame rate to its refresh rate
+    pub fn is_vsynced(&self) -> bool {
+        matches!(self.swapchain.present_mode(), PresentMode::Fifo | PresentMode::Relaxed)
+    }
+
+    /// Switches between windowed and borderless fullscreen on `monitor`, or
+    /// the one `--monitor` picked, restoring the previous window size when
+    /// leaving fullscreen
+    pub fn toggle_fullscreen(&mut self, monitor: MonitorId) {
+        let window = self.surface.window();
+        let monitor = self.exclusive_monitor.clone().unwrap_or(monitor);
+
+        match self.windowed_size.take() {
+            Some(size) => {
+                window.set_fullscreen(None);
+                window.set_inner_size(size);
+            },
+            None => {
+                self.windowed_size = window.get_inner_size();
+                window.set_fullscreen(Some(monitor));
+            },
+        }
+
+        // The surface extent changed, the swapchain and viewport must follow
+        self.swapchain_outdated = true;
+    }
+
+    /// Checks that the monitor `--monitor` picked is still connected after its
+    /// resolution or the window's size changed, as unplugging it or changing
+    /// its display mode does. Goes back to a window when it's gone.
+    pub fn handle_display_change(&mut self, events_loop: &EventsLoop) {
+        let name = match &self.exclusive_monitor {
+            Some(monitor) => monitor.get_name(),
+            None => return,
+        };
+        if events_loop.get_available_monitors().any(|monitor| monitor.get_name() == name) {
+            return;
+        }
+
+        println!("Warning: the monitor {} is gone, leaving fullscreen", name.as_deref().unwrap_or("?"));
+        self.exclusive_monitor = None;
+        if self.windowed_size.is_some() {
+            self.toggle_fullscreen(events_loop.get_primary_monitor());
+        }
+    }
+
+    /// Moves the point the scene is looked at from and turns the camera to look
+    /// along `direction`, starting with the next frame
+    pub fn set_camera(&mut self, position: [f32; 3], direction: [f32; 3]) {
+        self.scene.set_camera(position, direction);
+    }
+
+    /// Hides the cursor and keeps it within the window while it's `captured`,
+    /// so that the mouse can turn the camera all the way around
+    pub fn set_cursor_captured(&self, captured: bool) {
+        let window = self.surface.window();
+
+        if let Err(err) = window.grab_cursor(captured) {
+            println!("Warning: could not grab the cursor: {}", err);
+        }
+        window.hide_cursor(captured);
+    }
+
+    /// Switches between drawing filled polygons and only their edges
+    pub fn toggle_wireframe(&mut self) {
+        self.scene.toggle_wireframe();
+    }
+
+    /// Switches between drawing every triangle and only those facing the
+    /// camera, returning whether the back faces are now culled
+    pub fn toggle_culling(&mut self) -> bool {
+        self.scene.toggle_culling()
+    }
+
+    /// Widens the lines by `delta` pixels, or narrows them when negative,
+    /// returning their new width
+    pub fn change_line_width(&mut self, delta: f32) -> f32 {
+        self.scene.change_line_width(delta)
+    }
+
+    /// Switches between drawing the whole scene and only its center quarter
+    pub fn toggle_mask(&mut self) {
+        self.scene.toggle_mask();
+    }
+
+    /// Switches to the next topology the geometry is drawn with, returning it
+    pub fn cycle_topology(&mut self) -> Topology {
+        self.scene.cycle_topology()
+    }
+
+    /// Switches between spinning the geometry on the CPU and on the GPU,
+    /// returning whether it's now spun on the CPU
+    pub fn toggle_spin_on_cpu(&mut self) -> bool {
+        self.scene.toggle_spin_on_cpu()
+    }
+
+    /// Loads the texture again in the background, drawn once uploaded
+    pub fn reload_texture(&mut self) -> Result<(), AppError> {
+        self.scene.reload_texture()
+    }
+
+    /// The lines drawn over the next frame, cleared once it's drawn
+    pub fn debug_lines(&mut self) -> &mut DebugLines {
+        self.scene.debug_lines()
+    }
+
+    /// The text drawn over the next frame, cleared once it's drawn
+    pub fn text(&mut self) -> &mut TextRenderer {
+        self.scene.text()
+    }
+
+    /// Rebuilds the pipelines drawing the geometry from the current sources of
+    /// its shaders
+    #[cfg(debug_assertions)]
+    pub fn reload_shaders(&mut self) -> Result<(), AppError> {
+        self.scene.reload_shaders()
+    }
+
+    /// Copies the vertices currently drawn
+    pub fn read_vertices(&self) -> Result<Vec<Vertex>, AppError> {
+        self.scene.read_vertices()
+    }
+
+    /// Draws `vertices` from the next frame on. They're written into the
+    /// vertex buffer the last frame didn't read, so this only waits for the
+    /// GPU when the frame before it is still running.
+    pub fn update_vertices(&mut self, vertices: &[Vertex]) -> Result<(), AppError> {
+        let next = 1 - self.scene.current_vertex_buffer();
+        if let Some(fence) = &self.vertex_buffer_fences[next] {
+            fence.wait(None)?;
+        }
+
+        self.scene.update_vertices(vertices)
+    }
+
+    /// Saves the next frame presented as `screenshot_<unix time>.png`, if the
+    /// swapchain images can be copied from
+    pub fn request_screenshot(&mut self) -> Result<(), AppError> {
+        let capabilities = self.surface.capabilities(self.device.physical_device())?;
+
+        if !capabilities.supported_usage_flags.transfer_source {
+            println!("Warning: screenshots are not supported by this surface");
+        } else if format_channel_order(self.swapchain.format()) == ChannelOrder::Other {
+            println!("Warning: screenshots of {:?} images are not supported", self.swapchain.format());
+        } else {
+            self.screenshot_requested = true;
+        }
+
+        Ok(())
+    }
+
+    /// Advances the scene by a step of `delta_time` seconds
+    pub fn update(&mut self, delta_time: f32) {
+        self.time += delta_time;
+        self.last_step = delta_time;
+        self.scene.update(delta_time);
+    }
+
+    /// Animation time a frame drawn `alpha` of the way between the last two
+    /// updates shows, from 0 at the one before to 1 at the last
+    pub fn interpolated_time(&self, alpha: f32) -> f32 {
+        self.time - (1.0 - alpha) * self.last_step
+    }
+
+    /// Records, submits and presents one frame, drawn `alpha` of the way
+    /// between the last two updates
+    pub fn render(&mut self, alpha: f32) -> Result<(), AppError> {
+        if self.scene.poll_texture_reload()? {
+            self.scene.rebind_texture(&mut self.targets)?;
+            println!("Texture reloaded");
+        }
+
+        if self.swapchain_outdated {
+            self.recreate_swapchain()?;
+
+            if self.swapchain_outdated {
+                return Ok(());
+            }
+        }
+
+        let acquire_start = Instant::now();
+        let acquired = swapchain::acquire_next_image(self.swapchain.clone(), None);
+        self.acquire_time = acquire_start.elapsed();
+
+        let (image_num, acquire_future) =
+            match acquired {
+                Ok(r) => r,
+                Err(AcquireError::OutOfDate) => {
+                    self.swapchain_outdated = true;
+                    return Ok(());
+                },
+                Err(err) => return Err(err.into()),
+            };
+
+        // Waits for the GPU to finish the previous frame rendered into this image
+        if let Some(image_fence) = &self.fences[image_num] {
+            image_fence.wait(None)?;
+        }
+
+        // Which makes its histogram readable. Empty until a frame was drawn
+        // into the image since the targets were created.
+        if self.last_histogram_print.elapsed() >= HISTOGRAM_PRINT_INTERVAL {
+            if let Some(bins) = self.scene.read_histogram(&self.targets, image_num)? {
+                let line = histogram::format(&bins);
+                if !line.is_empty() {
+                    println!("{}", line);
+                    self.last_histogram_print = Instant::now();
+                }
+            }
+        }
+
+        let previous_future = match self.fences[self.previous_fence_index].clone() {
+            Some(fence) => Box::new(fence) as Box<dyn GpuFuture + Send + Sync>,
+            None => {
+                let mut now = sync::now(self.device.clone());
+                now.cleanup_finished();
+
+                Box::new(now) as Box<dyn GpuFuture + Send + Sync>
+            },
+        };
+
+        // Makes sure sampling never reads an image which isn't uploaded yet
+        let previous_future = match self.scene.take_pending_uploads() {
+            Some(uploads) => Box::new(previous_future.join(uploads)),
+            None => previous_future,
+        };
+
+        let time = self.interpolated_time(alpha);
+        let record_start = Instant::now();
+        let command_buffer = self.scene.draw(&mut self.targets, image_num, time)?;
+        self.record_time = record_start.elapsed();
+        self.scene.debug_lines().clear();
+        self.scene.text().clear();
+
+        // The image's previous frame is done, its timestamps can be read
+        let begin_timestamp = match &mut self.gpu_timer {
+            Some(gpu_timer) => gpu_timer.begin_frame(image_num)?,
+            None => None,
+        };
+
+        let future = Box::new(previous_future.join(acquire_future)) as Box<dyn GpuFuture + Send + Sync>;
+        let future = match begin_timestamp {
+            Some(begin_timestamp) =>
+                Box::new(future.then_execute(self.queue.clone(), begin_timestamp)?),
+            None => future,
+        };
+
+        let future = future.then_execute(self.queue.clone(), command_buffer)?;
+
+        let future = match &mut self.gpu_timer {
+            Some(gpu_timer) if gpu_timer.is_measuring(image_num) =>
+                Box::new(future.then_execute(self.queue.clone(), gpu_timer.end_frame(image_num)?)?)
+                    as Box<dyn GpuFuture + Send + Sync>,
+            _ => Box::new(future),
+        };
+
+        // The copy runs right after drawing, within the frame's own chain of
+        // futures, so vulkano moves the image into the layout copies need and
+        // back to the one presenting needs
+        let screenshot_buffer =
+            if self.screenshot_requested {
+                self.screenshot_requested = false;
+
+                let [width, height] = self.swapchain.dimensions();
+                let buffer =
+                    CpuAccessibleBuffer::from_iter(
+                        self.device.clone(), BufferUsage::all(),
+                        (0 .. width * height * 4).map(|_| 0u8)
+                    )?;
+
+                Some(buffer)
+            } else {
+                None
+            };
+
+        let future = match &screenshot_buffer {
+            Some(buffer) => {
+                let copy_command_buffer =
+                    AutoCommandBufferBuilder::new(self.device.clone(), self.queue.family())?
+                        .copy_image_to_buffer(self.images[image_num].clone(), buffer.clone())?
+                        .build()?;
+
+                Box::new(future.then_execute(self.queue.clone(), copy_command_buffer)?)
+                    as Box<dyn GpuFuture + Send + Sync>
+            },
+            None => Box::new(future),
+        };
+
+        let future =
+            Box::new(
+                future
+                    .then_swapchain_present(self.queue.clone(), self.swapchain.clone(), image_num)
+            ) as Box<dyn GpuFuture + Send + Sync>;
+
+        self.fences[image_num] = match future.then_signal_fence_and_flush() {
+            Ok(future) => {
+                // A screenshot failing to save, to a full disk or an unwritable
+                // directory, isn't worth stopping over
+                if let Some(buffer) = screenshot_buffer {
+                    let saved =
+                        future.wait(None).map_err(AppError::from)
+                            .and_then(|()| {
+                                save_screenshot(&buffer, self.swapchain.dimensions(), self.swapchain.format())
+                            });
+                    if let Err(err) = saved {
+                        println!("Warning: could not save the screenshot: {}", err);
+                    }
+                }
+
+                let fence = Arc::new(future);
+                self.vertex_buffer_fences[self.scene.current_vertex_buffer()] = Some(fence.clone());
+                self.targets.track_frame(&fence);
+
+                Some(fence)
+            },
+            Err(FlushError::OutOfDate) => {
+                self.swapchain_outdated = true;
+                None
+            },
+            Err(err @ FlushError::DeviceLost)
+            | Err(err @ FlushError::OomError(OomError::OutOfDeviceMemory)) =>
+                return Err(err.into()),
+            Err(err) => {
+                println!("Error: FlushError: {:?}", err);
+                None
+            },
+        };
+        self.previous_fence_index = image_num;
+
+        Ok(())
+    }
+}
+
+/// Saves the content of a swapchain image of `dimensions` and `format` copied
+/// into `buffer` as `screenshot_<unix time>.png`
+fn save_screenshot(buffer: &CpuAccessibleBuffer<[u8]>, dimensions: [u32; 2], format: Format)
+    -> Result<(), AppError>
+{
+    let mut pixels = buffer.read()?.to_vec();
+
+    match format_channel_order(format) {
+        ChannelOrder::Rgba => (),
+        ChannelOrder::Bgra => {
+            for pixel in pixels.chunks_mut(4) {
+                pixel.swap(0, 2);
+            }
+        },
+        ChannelOrder::Other =>
+            return Err(AppError::InvalidImageData("the swapchain format has no RGBA equivalent")),
+    }
+
+    let [width, height] = dimensions;
+    let image =
+        ImageBuffer::<Rgba<u8>, _>::from_raw(width, height, pixels)
+            .ok_or(AppError::InvalidImageData("buffer is too small for the image"))?;
+
+    let timestamp = SystemTime::now().duration_since(UNIX_EPOCH).map_or(0, |time| time.as_secs());
+    let path = format!("screenshot_{}.png", timestamp);
+    image.save(&path)?;
+    println!("Saved {}", path);
+
+    Ok(())
+}
+
+/// How the channels of pixels of `format` are ordered in memory. The packed
+/// A8B8G8R8 formats are stored in a little endian 32 bits word, red first.
+pub fn format_channel_order(format: Format) -> ChannelOrder {
+    match format {
+        Format::R8G8B8A8Unorm | Format::R8G8B8A8Snorm | Format::R8G8B8A8Uscaled | Format::R8G8B8A8Sscaled
+        | Format::R8G8B8A8Uint | Format::R8G8B8A8Sint | Format::R8G8B8A8Srgb
+        | Format::A8B8G8R8UnormPack32 | Format::A8B8G8R8SnormPack32 | Format::A8B8G8R8UscaledPack32
+        | Format::A8B8G8R8SscaledPack32 | Format::A8B8G8R8UintPack32 | Format::A8B8G8R8SintPack32
+        | Format::A8B8G8R8SrgbPack32 => ChannelOrder::Rgba,
+        Format::B8G8R8A8Unorm | Format::B8G8R8A8Snorm | Format::B8G8R8A8Uscaled | Format::B8G8R8A8Sscaled
+        | Format::B8G8R8A8Uint | Format::B8G8R8A8Sint | Format::B8G8R8A8Srgb => ChannelOrder::Bgra,
+        _ => ChannelOrder::Other,
+    }
+}
+
+/// The instance, along with the callback printing its validation messages in
+/// debug builds, only reported for as long as it's alive
+pub struct VulkanContext {
+    pub instance: Arc<Instance>,
+    #[cfg(debug_assertions)]
+    _debug_callback: Option<DebugCallback>,
+}
+
+impl VulkanContext {
+    /// Creates the instance with `extensions`, see `create_instance`
+    pub fn new(extensions: InstanceExtensions) -> Result<Self, AppError> {
+        let instance = create_instance(extensions)?;
+
+        Ok(VulkanContext {
+            #[cfg(debug_assertions)]
+            _debug_callback: create_debug_callback(&instance),
+            instance,
+        })
+    }
+
+    /// Opens the device `config` chooses with `extensions`, without any of
+    /// the optional features
+    pub fn open_device(&self, config: &Config, extensions: &DeviceExtensions)
+        -> Result<DeviceAndQueues, AppError>
+    {
+        let physical_device = choose_physical_device(&self.instance, config)?;
+        create_device(physical_device, extensions, &Features::none())
+    }
+
+    /// Opens the device `config` chooses with `extensions`, and the features
+    /// needed to draw the scene `config` describes
+    pub fn open_scene_device(&self, config: &Config, extensions: &DeviceExtensions)
+        -> Result<DeviceAndQueues, AppError>
+    {
+        let physical_device = choose_physical_device(&self.instance, config)?;
+        let features = scene_features(physical_device, config)?;
+        create_device(physical_device, extensions, &features)
+    }
+}
+
+/// Creates the instance with `extensions`, plus the validation layer in debug
+/// builds when it's installed
+pub fn create_instance(extensions: InstanceExtensions) -> Result<Arc<Instance>, AppError> {
+    let debug = cfg!(debug_assertions);
+
+    let extensions = InstanceExtensions {
+        // Needed to receive the validation layer messages
+        ext_debug_report: debug && InstanceExtensions::supported_by_core()?.ext_debug_report,
+        .. extensions
+    };
+
+    let layers =
+        if debug && is_layer_available(VALIDATION_LAYER)? {
+            vec![VALIDATION_LAYER]
+        } else {
+            if debug {
+                println!(
+                    "Warning: {} is not installed, continuing without validation",
+                    VALIDATION_LAYER);
+            }
+
+            vec![]
+        };
+
+    Ok(Instance::new(None, &extensions, layers.iter().cloned())?)
+}
+
+fn is_layer_available(name: &str) -> Result<bool, AppError> {
+    Ok(instance::layers_list()?.any(|layer| layer.name() == name))
+}
+
+/// Prints the messages reported by the validation layer, if the debug report
+/// extension could be loaded
+#[cfg(debug_assertions)]
+fn create_debug_callback(instance: &Arc<Instance>) -> Option<DebugCallback> {
+    let message_types = MessageTypes {
+        error: true,
+        warning: true,
+        performance_warning: true,
+        information: false,
+        debug: false,
+    };
+
+    let callback = DebugCallback::new(instance, message_types, |message| {
+        let severity =
+            if message.ty.error {
+                "error"
+            } else if message.ty.warning || message.ty.performance_warning {
+                "warning"
+            } else if message.ty.information {
+                "information"
+            } else {
+                "debug"
+            };
+
+        let message_type =
+            if message.ty.performance_warning { "performance" } else { "general" };
+
+        println!(
+            "Vulkan {} ({}) [{}]: {}",
+            severity, message_type, message.layer_prefix, message.description);
+    });
+
+    match callback {
+        Ok(callback) => Some(callback),
+        Err(err) => {
+            println!("Warning: Could not register the debug callback: {}", err);
+            None
+        },
+    }
+}
+
+/// Picks the physical device at the index `config` gives if any, or else the
+/// most capable one able to render: GPUs of the kind `config` prefers win over
+/// the others, then the biggest supported image size decides. With
+/// `--choose-gpu`, the user picks among those able to render instead.
+pub fn choose_physical_device<'a>(instance: &'a Arc<Instance>, config: &Config)
+    -> Result<PhysicalDevice<'a>, AppError>
+{
+    if PhysicalDevice::enumerate(instance).next().is_none() {
+        return Err(AppError::NoDevice);
+    }
+
+    let preference = config.device_preference;
+    let (chosen_physical_device, _score) = match config.gpu_index {
+        Some(index) => {
+            let device = match PhysicalDevice::enumerate(instance).nth(index) {
+                Some(device) => device,
+                None => {
+                    eprintln!("Available devices:");
+                    for device in PhysicalDevice::enumerate(instance) {
+                        eprintln!("  {}: {} ({:?})", device.index(), device.name(), device.ty());
+                    }
+
+                    return Err(AppError::DeviceIndexOutOfRange {
+                        index,
+                        count: PhysicalDevice::enumerate(instance).count(),
+                    });
+                },
+            };
+
+            if !device.queue_families().any(|q| q.supports_graphics()) {
+                return Err(AppError::NoSuitableDevice(
+                    format!("device {} has no GRAPHICS_BIT queue family", index)
+                ));
+            }
+
+            (device, score_physical_device(device, preference))
+        },
+        None => {
+            let candidates: Vec<_> =
+                PhysicalDevice::enumerate(instance)
+                    .filter(|device| device.queue_families().any(|q| q.supports_graphics()))
+                    .map(|device| (device, score_physical_device(device, preference)))
+                    .collect();
+            let best = candidates.iter().copied().max_by_key(|&(_, score)| score)
+                .ok_or_else(|| AppError::NoSuitableDevice(
+                    "no physical device with a GRAPHICS_BIT queue family found".into()
+                ))?;
+
+            if config.choose_gpu && candidates.len() > 1 {
+                prompt_physical_device(&candidates, best)?
+            } else {
+                best
+            }
+        },
+    };
+
+    #[cfg(debug_assertions)]
+    {
+        println!(
+            "Chosen device: {:?} (score {}): {:?}",
+            chosen_physical_device.name(),
+            _score,
+            chosen_physical_device);
+
+        // Integrated GPUs usually have no heap of their own and share the
+        // system memory, reported as device local
+        let mut device_local_size = 0;
+        for heap in chosen_physical_device.memory_heaps() {
+            println!(
+                "Memory heap {}: {}{}",
+                heap.id(),
+                format_size(heap.size()),
+                if heap.is_device_local() { ", device local" } else { "" });
+
+            if heap.is_device_local() {
+                device_local_size += heap.size();
+            }
+        }
+        println!("Total device local memory: {}", format_size(device_local_size));
+
+        println!();
+    }
+
+    Ok(chosen_physical_device)
+}
+
+/// Lists the `candidates` with their scores and reads the index of one from
+/// stdin, asking again until it's one of theirs. An empty line, or stdin being
+/// closed, picks `default`.
+fn prompt_physical_device<'a>(candidates: &[(PhysicalDevice<'a>, u32)], default: (PhysicalDevice<'a>, u32))
+    -> Result<(PhysicalDevice<'a>, u32), AppError>
+{
+    let default_index = default.0.index();
+
+    println!("Devices able to render:");
+    for &(device, score) in candidates {
+        let marker = if device.index() == default_index { ", default" } else { "" };
+        println!("  {}: {} ({:?}, score {}{})", device.index(), device.name(), device.ty(), score, marker);
+    }
+
+    let stdin = io::stdin();
+    let mut lines = stdin.lock().lines();
+    loop {
+        print!("Device to use [{}]: ", default_index);
+        io::stdout().flush()?;
+
+        let line = match lines.next() {
+            Some(line) => line?,
+            // Reached the end of stdin, the output doesn't end with a newline
+            None => {
+                println!();
+                break;
+            },
+        };
+
+        let line = line.trim();
+        if line.is_empty() {
+            break;
+        }
+
+        let chosen =
+            line.parse::<usize>().ok()
+                .and_then(|index| candidates.iter().copied().find(|(device, _)| device.index() == index));
+        match chosen {
+            Some(chosen) => return Ok(chosen),
+            None => println!("'{}' is not the index of one of the devices listed", line),
+        }
+    }
+
+    Ok(default)
+}
+
+/// Formats a size in bytes in GiB, or in MiB below 1 GiB
+#[cfg(debug_assertions)]
+fn format_size(bytes: usize) -> String {
+    const MIB: f64 = (1 << 20) as f64;
+    const GIB: f64 = (1 << 30) as f64;
+
+    let bytes = bytes as f64;
+    if bytes >= GIB {
+        format!("{:.1} GiB", bytes / GIB)
+    } else {
+        format!("{:.0} MiB", bytes / MIB)
+    }
+}
+
+fn score_physical_device(device: PhysicalDevice, preference: DevicePreference) -> u32 {
+    let preferred_type = match preference {
+        DevicePreference::Discrete => PhysicalDeviceType::DiscreteGpu,
+        DevicePreference::Integrated => PhysicalDeviceType::IntegratedGpu,
+    };
+
+    // Larger than any `max_image_dimension_2d`, so that any GPU of the
+    // preferred type beats the others
+    let type_bonus = if device.ty() == preferred_type { 100_000 } else { 0 };
+
+    type_bonus + device.limits().max_image_dimension_2d()
+}
+
+/// Returns `needed` when the device supports all of it, otherwise an error
+/// naming the features it lacks
+pub fn require_features(physical_device: PhysicalDevice, needed: &Features) -> Result<Features, AppError> {
+    let missing = needed.difference(physical_device.supported_features());
+    if missing == Features::none() {
+        return Ok(needed.clone());
+    }
+
+    // Only the features this program asks for are named, vulkano has no way
+    // of listing them
+    let requested = [
+        ("geometry_shader", missing.geometry_shader),
+        ("fill_mode_non_solid", missing.fill_mode_non_solid),
+        ("wide_lines", missing.wide_lines),
+        ("large_points", missing.large_points),
+        ("sampler_anisotropy", missing.sampler_anisotropy),
+    ];
+    let names =
+        requested.iter()
+            .filter(|&&(_, is_missing)| is_missing)
+            .map(|&(name, _)| name)
+            .collect::<Vec<_>>();
+
+    Err(AppError::MissingFeature(
+        if names.is_empty() { format!("{:?}", missing) } else { names.join(", ") }
+    ))
+}
+
+/// The features drawing the scene `config` describes needs, plus the ones
+/// enabling optional modes when they're supported and the scene can switch to
+/// them. Enabling every supported feature instead could slow down drivers for
+/// nothing.
+pub fn scene_features(physical_device: PhysicalDevice, config: &Config) -> Result<Features, AppError> {
+    // Showing the normals takes a geometry shader
+    let needed = Features { geometry_shader: config.show_normals, .. Features::none() };
+    let mut features = require_features(physical_device, &needed)?;
+    let supported = physical_device.supported_features();
+
+    // Only the window's keys switch to the wireframe, the lines and the
+    // points, the other modes always draw filled triangles
+    let interactive = matches!(config.mode, Mode::Window);
+
+    // The wireframe mode is only disabled without it
+    if interactive {
+        features.fill_mode_non_solid = supported.fill_mode_non_solid;
+        if !features.fill_mode_non_solid {
+            println!("Warning: fill_mode_non_solid is not supported, wireframe mode is disabled");
+        }
+    }
+
+    // Lines and points are only drawn a pixel wide without them, the normals
+    // being lines too
+    features.wide_lines = (interactive || config.show_normals) && supported.wide_lines;
+    features.large_points = interactive && supported.large_points;
+
+    // The textures are only filtered trilinearly without it
+    if config.anisotropy.is_some() {
+        features.sampler_anisotropy = supported.sampler_anisotropy;
+        if !features.sampler_anisotropy {
+            println!("Warning: sampler_anisotropy is not supported, the textures are filtered trilinearly");
+        }
+    }
+
+    Ok(features)
+}
+
+/// Creates the logical device drawing the scene along with its graphics queue
+/// and a queue for uploads, with `features` enabled. Doesn't involve any
+/// surface, so that it also works offscreen.
+///
+/// The upload queue comes from a family without graphics support if there's
+/// one, those usually map to dedicated copy engines which run alongside the
+/// rendering. Otherwise it's a second queue of the graphics family when it has
+/// two, and the graphics queue is returned twice when it only has one. The
+/// upload queue gets a lower priority, for background uploads to give way to
+/// the rendering where the driver honors it.
+pub fn create_device(physical_device: PhysicalDevice, extensions: &DeviceExtensions, features: &Features)
+    -> Result<DeviceAndQueues, AppError>
+{
+    let chosen_family = physical_device.queue_families()
+        .find(|&q| q.supports_graphics())
+        .ok_or_else(|| AppError::NoSuitableDevice(
+            "no family supporting GRAPHICS_BIT found in chosen device".into()
+        ))?;
+
+    let transfer_family = physical_device.queue_families()
+        .find(|&q| q.explicitly_supports_transfers() && !q.supports_graphics());
+
+    let mut families = vec![(chosen_family, RENDER_QUEUE_PRIORITY)];
+    match transfer_family {
+        Some(transfer_family) => families.push((transfer_family, UPLOAD_QUEUE_PRIORITY)),
+        None if chosen_family.queues_count() >= 2 => families.push((chosen_family, UPLOAD_QUEUE_PRIORITY)),
+        None => (),
+    }
+
+    let (device, mut queues) =
+        Device::new(
+            physical_device,
+            features,
+            extensions,
+            families
+        )?;
+
+    let queue = queues.next()
+        .ok_or_else(|| AppError::NoSuitableDevice("no queue found in chosen family".into()))?;
+
+    let transfer_queue = queues.next().unwrap_or_else(|| queue.clone());
+
+    #[cfg(debug_assertions)]
+    {
+        if transfer_queue.family().id() != queue.family().id() {
+            println!("Uploading on the dedicated queue family {}", transfer_queue.family().id());
+        } else if transfer_queue.id_within_family() != queue.id_within_family() {
+            println!("No dedicated transfer queue family, uploading on a second graphics queue");
+        } else {
+            println!("No dedicated transfer queue family, uploading on the graphics queue");
+        }
+
+        println!();
+    }
+
+    Ok((device, queue, transfer_queue))
+}
+
+/// Creates a swapchain of `format` images presenting to `surface`, sized like
+/// its window, which must be one of the formats the surface supports. Asks for
+/// `image_count` images when given, clamped to what the surface allows, and
+/// presents with `present_mode` when given and supported. The images can be
+/// used as `usage` tells.
+pub fn create_swapchain(
+    device: &Arc<Device>,
+    queue: &Arc<Queue>,
+    surface: Arc<Surface<Window>>,
+    format: Format,
+    present_mode: Option<PresentMode>,
+    image_count: Option<u32>,
+    usage: SwapchainUsage
+) -> Result<SwapchainAndImages, AppError> {
+    let capabilities = surface.capabilities(device.physical_device())?;
+    let desired_usage = choose_usage(&capabilities, usage)?;
+
+    // The window manager may not give the size asked for, what the window
+    // ended up with is clamped to what the surface supports
+    let dimensions = get_dimensions(&surface, &capabilities);
+    let alpha = capabilities.supported_composite_alpha.iter().next()
+        .ok_or_else(|| AppError::NoSuitableDevice(
+            "the surface supports no composite alpha mode".into()
+        ))?;
+    let present_mode = choose_present_mode(&capabilities, present_mode);
+
+    // Mailbox needs a spare image to draw into while one is displayed and
+    // another one waits for the next vertical blank. No maximum means any
+    // number of images.
+    let requested_image_count = image_count.unwrap_or(match present_mode {
+        PresentMode::Mailbox => capabilities.min_image_count + 1,
+        _ => capabilities.min_image_count,
+    });
+    let clamped_image_count =
+        capabilities.max_image_count
+            .map_or(requested_image_count, |max| requested_image_count.min(max))
+            .max(capabilities.min_image_count);
+
+    let (swapchain, images) =
+        Swapchain::new(
+            device.clone(), surface, clamped_image_count,
+            format, dimensions, 1, desired_usage, queue,
+            SurfaceTransform::Identity, alpha, present_mode, true, None
+        )?;
+
+    // The count asked for is only a minimum, drivers may create more
+    if image_count.is_some() || cfg!(debug_assertions) {
+        println!(
+            "Presenting with {:?} on {} images, {} asked for", present_mode, images.len(), clamped_image_count
+        );
+        println!();
+    }
+
+    Ok((swapchain, images))
+}
+
+/// The usage the swapchain images are created with: all of `usage.needed`,
+/// failing with the usages the surface lacks, plus what it supports of
+/// `usage.optional`. Asking for more than needed may keep the driver from
+/// laying the images out the best way for presenting.
+fn choose_usage(capabilities: &Capabilities, usage: SwapchainUsage) -> Result<ImageUsage, AppError> {
+    let supported = capabilities.supported_usage_flags;
+
+    let missing: Vec<&str> =
+        usage_flags(usage.needed).iter().zip(usage_flags(supported).iter())
+            .filter(|((_, needed), (_, supported))| *needed && !*supported)
+            .map(|((name, _), _)| *name)
+            .collect();
+    if !missing.is_empty() {
+        return Err(AppError::MissingSwapchainUsage(missing.join(", ")));
+    }
+
+    let (needed, optional) = (usage.needed, usage.optional);
+    let pick = |needed: bool, optional: bool, supported: bool| needed || (optional && supported);
+    Ok(ImageUsage {
+        transfer_source: pick(needed.transfer_source, optional.transfer_source, supported.transfer_source),
+        transfer_destination:
+            pick(needed.transfer_destination, optional.transfer_destination, supported.transfer_destination),
+        sampled: pick(needed.sampled, optional.sampled, supported.sampled),
+        storage: pick(needed.storage, optional.storage, supported.storage),
+        color_attachment:
+            pick(needed.color_attachment, optional.color_attachment, supported.color_attachment),
+        depth_stencil_attachment:
+            pick(
+                needed.depth_stencil_attachment, optional.depth_stencil_attachment,
+                supported.depth_stencil_attachment
+            ),
+        transient_attachment:
+            pick(needed.transient_attachment, optional.transient_attachment, supported.transient_attachment),
+        input_attachment:
+            pick(needed.input_attachment, optional.input_attachment, supported.input_attachment),
+    })
+}
+
+/// Every usage an image can have, named, along with whether `usage` has it
+fn usage_flags(usage: ImageUsage) -> [(&'static str, bool); 8] {
+    [
+        ("transfer source", usage.transfer_source),
+        ("transfer destination", usage.transfer_destination),
+        ("sampled", usage.sampled),
+        ("storage", usage.storage),
+        ("color attachment", usage.color_attachment),
+        ("depth stencil attachment", usage.depth_stencil_attachment),
+        ("transient attachment", usage.transient_attachment),
+        ("input attachment", usage.input_attachment),
+    ]
+}
+
+/// Returns the size the swapchain images must have. Most platforms report it
+/// through `current_extent`, but on Wayland it's always `None` and the surface
+/// takes whatever size the swapchain has, so it's taken from the window: its
+/// logical size times its HiDPI factor, which changes between monitors.
+pub fn get_dimensions(surface: &Surface<Window>, capabilities: &Capabilities) -> [u32; 2] {
+    if let Some(extent) = capabilities.current_extent {
+        return extent;
+    }
+
+    let window = surface.window();
+    let [width, height] = match window.get_inner_size() {
+        Some(size) => {
+            let size: (u32, u32) = size.to_physical(window.get_hidpi_factor()).into();
+            [size.0, size.1]
+        },
+        // The window no longer exists, any supported size will do
+        None => capabilities.min_image_extent,
+    };
+
+    let [min_width, min_height] = capabilities.min_image_extent;
+    let [max_width, max_height] = capabilities.max_image_extent;
+
+    [width.max(min_width).min(max_width), height.max(min_height).min(max_height)]
+}
+
+/// Returns the monitor at `index` among the ones `events_loop` lists, to be
+/// filled in fullscreen.
+///
+/// winit 0.19 can't change the video mode, that came with exclusive
+/// fullscreen in 0.20, and vulkano 0.13 doesn't know
+/// `VK_EXT_full_screen_exclusive`. So the monitor keeps its current, usually
+/// native, resolution, and the swapchain gets exactly that size. A window
+/// covering its whole monitor at a matching size is what lets compositors
+/// stop composing it and flip its images straight to the display. That saves
+/// a copy and usually a frame of latency, which is what exclusive mode is
+/// after, unlike a borderless window of any other size.
+fn choose_monitor(events_loop: &EventsLoop, index: usize) -> Result<MonitorId, AppError> {
+    let monitors: Vec<MonitorId> = events_loop.get_available_monitors().collect();
+    let count = monitors.len();
+
+    let monitor = monitors.into_iter().nth(index)
+        .ok_or_else(|| AppError::InvalidArgument(
+            format!("--monitor {} but there are {} monitors", index, count)
+        ))?;
+
+    let (width, height): (u32, u32) = monitor.get_dimensions().into();
+    println!(
+        "Fullscreen on the monitor {} at {}x{}", monitor.get_name().as_deref().unwrap_or("?"), width, height
+    );
+
+    Ok(monitor)
+}
+
+/// Returns the size the swapchain images must have to fill `monitor` at its
+/// resolution, which the surface's extent overrides when it reports one
+fn monitor_dimensions(monitor: &MonitorId, capabilities: &Capabilities) -> [u32; 2] {
+    if let Some(extent) = capabilities.current_extent {
+        return extent;
+    }
+
+    let (width, height): (u32, u32) = monitor.get_dimensions().into();
+    let [min_width, min_height] = capabilities.min_image_extent;
+    let [max_width, max_height] = capabilities.max_image_extent;
+
+    [width.max(min_width).min(max_width), height.max(min_height).min(max_height)]
+}
+
+/// Returns `requested` when the surface supports it, warning when it doesn't.
+/// Otherwise prefers `Mailbox`, which never blocks nor tears by replacing the
+/// image waiting to be displayed, then `Immediate`, which tears. `Fifo` is the
+/// only mode always supported.
+fn choose_present_mode(capabilities: &Capabilities, requested: Option<PresentMode>) -> PresentMode {
+    let modes = capabilities.present_modes;
+
+    match requested {
+        Some(mode) if modes.supports(mode) => return mode,
+        Some(mode) => println!("Warning: the surface doesn't support {:?}, ignoring it", mode),
+        None => (),
+    }
+
+    if modes.mailbox {
+        PresentMode::Mailbox
+    } else if modes.immediate {
+        PresentMode::Immediate
+    } else {
+        PresentMode::Fifo
+    }
+}
+
+/// Picks an 8 bits per channel swapchain format of `encoding`, falling back
+/// to the first one supported. The shaders work with linear colors: with an
+/// sRGB format the hardware encodes them with the sRGB curve the monitor
+/// expects when writing them, while with a UNORM format the linear values are
+/// displayed as-is, making dark and mid tones look too dark.
+pub fn choose_swapchain_format(capabilities: &Capabilities, encoding: SwapchainEncoding) -> Format {
+    let candidates = match encoding {
+        SwapchainEncoding::Srgb => [Format::B8G8R8A8Srgb, Format::R8G8B8A8Srgb],
+        SwapchainEncoding::Linear => [Format::B8G8R8A8Unorm, Format::R8G8B8A8Unorm],
+    };
+
+    let chosen =
+        candidates.iter()
+            .find(|&&candidate|
+                capabilities.supported_formats.contains(&(candidate, ColorSpace::SrgbNonLinear)));
+
+    match chosen {
+        Some(&format) => format,
+        None => {
+            let fallback = capabilities.supported_formats[0].0;
+            println!("Warning: no {} swapchain format is supported, using {:?}", encoding.name(), fallback);
+            println!("Supported formats:");
+            for (format, color_space) in &capabilities.supported_formats {
+                println!("    {:?} ({:?})", format, color_space);
+            }
+
+            fallback
+        },
+    }
+}
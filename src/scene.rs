@@ -0,0 +1,2848 @@
+// Build-in modules
+use std::cmp::Ordering;
+#[cfg(debug_assertions)]
+use std::ffi::CStr;
+#[cfg(debug_assertions)]
+use std::path::Path;
+use std::iter;
+use std::ops::Range;
+use std::path::PathBuf;
+use std::sync::Arc;
+use std::sync::mpsc::{self, Receiver, TryRecvError};
+use std::thread;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+// External modules
+use cgmath::{InnerSpace, Matrix, Matrix4, MetricSpace, Point3, Rad, SquareMatrix, Transform as _, Vector3};
+use image::RgbaImage;
+use rayon::ThreadPool;
+use rayon::ThreadPoolBuilder;
+use rayon::prelude::*;
+#[cfg(debug_assertions)]
+use shaderc::ShaderKind;
+use vulkano::buffer::BufferAccess;
+use vulkano::buffer::BufferUsage;
+use vulkano::buffer::CpuAccessibleBuffer;
+use vulkano::buffer::DeviceLocalBuffer;
+use vulkano::buffer::TypedBufferAccess;
+use vulkano::command_buffer::AutoCommandBuffer;
+use vulkano::command_buffer::AutoCommandBufferBuilder;
+use vulkano::command_buffer::CommandBuffer;
+use vulkano::command_buffer::DynamicState;
+use vulkano::descriptor::DescriptorSet;
+use vulkano::descriptor::PipelineLayoutAbstract;
+use vulkano::descriptor::descriptor_set::PersistentDescriptorSet;
+use vulkano::descriptor::pipeline_layout::PipelineLayout;
+use vulkano::device::Device;
+use vulkano::device::Queue;
+use vulkano::format::ClearValue;
+use vulkano::format::Format;
+use vulkano::format::FormatTy;
+use vulkano::framebuffer::Framebuffer;
+use vulkano::framebuffer::FramebufferAbstract;
+use vulkano::framebuffer::LoadOp;
+use vulkano::framebuffer::RenderPassAbstract;
+use vulkano::framebuffer::Subpass;
+use vulkano::image::AttachmentImage;
+use vulkano::image::ImageViewAccess;
+use vulkano::image::ImmutableImage;
+use vulkano::pipeline::ComputePipeline;
+use vulkano::pipeline::GraphicsPipeline;
+use vulkano::pipeline::GraphicsPipelineAbstract;
+use vulkano::pipeline::shader::GraphicsEntryPoint;
+#[cfg(debug_assertions)]
+use vulkano::pipeline::shader::{GraphicsShaderType, ShaderModule};
+#[cfg(debug_assertions)]
+use vulkano::descriptor::descriptor::ShaderStages;
+use vulkano::pipeline::blend::{AttachmentBlend, BlendFactor, BlendOp};
+use vulkano::pipeline::depth_stencil::{Compare, DepthStencil, Stencil, StencilOp};
+use vulkano::pipeline::vertex::BufferlessDefinition;
+use vulkano::pipeline::vertex::BufferlessVertices;
+use vulkano::pipeline::vertex::OneVertexOneInstanceDefinition;
+use vulkano::pipeline::viewport::Scissor;
+use vulkano::pipeline::viewport::Viewport;
+use vulkano::sampler::{Filter, MipmapMode, Sampler, SamplerAddressMode};
+use vulkano::sync::FlushError;
+use vulkano::sync::GpuFuture;
+
+// Internal modules
+use crate::assets::{self, Assets};
+use crate::config::Config;
+use crate::debug_lines::DebugLines;
+use crate::error::AppError;
+use crate::histogram::{Histogram, HistogramTarget};
+#[cfg(debug_assertions)]
+use crate::hot_reload;
+use crate::particles::Particles;
+use crate::skybox::Skybox;
+use crate::renderer::FrameFence;
+use crate::text::TextRenderer;
+use crate::uniform_ring::UniformRing;
+
+/// Laid out as declared, since the wave compute shader reads it as floats
+#[derive(Default, Copy, Clone)]
+#[repr(C)]
+pub struct Vertex {
+    position: [f32; 3],
+    tex_coord: [f32; 2],
+    /// Alpha below 1 only shows through pipelines blending it
+    color: [f32; 4],
+    normal: [f32; 3],
+    /// Where the U texture coordinate grows along the surface, filled in by
+    /// `compute_tangents` once the geometry is built
+    tangent: [f32; 3],
+}
+vulkano::impl_vertex!(Vertex, position, tex_coord, color, normal, tangent);
+
+/// Indices into the vertex buffer, 32 bits wide only when 16 aren't enough
+enum Indices {
+    U16(Vec<u16>),
+    U32(Vec<u32>),
+}
+
+/// `Indices` once uploaded, into device local memory when they never change
+/// or host visible memory when they're sorted again every frame
+enum IndexBuffer {
+    U16(Arc<dyn TypedBufferAccess<Content = [u16]> + Send + Sync>),
+    U32(Arc<dyn TypedBufferAccess<Content = [u32]> + Send + Sync>),
+}
+
+/// Where the vertices are drawn from
+#[derive(Clone)]
+enum VertexBuffer {
+    /// Uploaded once, the fastest for the GPU to read, and written by the GPU
+    /// wave
+    DeviceLocal(Arc<DeviceLocalBuffer<[Vertex]>>),
+    /// Written by the CPU every time the vertices change
+    HostVisible(Arc<CpuAccessibleBuffer<[Vertex]>>),
+}
+
+impl VertexBuffer {
+    fn access(&self) -> Arc<dyn BufferAccess + Send + Sync> {
+        match self {
+            VertexBuffer::DeviceLocal(buffer) => buffer.clone(),
+            VertexBuffer::HostVisible(buffer) => buffer.clone(),
+        }
+    }
+}
+
+/// What the scene is made of
+#[derive(Clone)]
+pub enum Geometry {
+    /// The textured quad
+    Quad,
+    /// A grid of about `vertex_count` vertices filling the window
+    Grid { vertex_count: u32 },
+    /// The mesh of the Wavefront OBJ file at `path`
+    Mesh { path: String },
+    /// The first mesh of the glTF file at `path`, textured with its material
+    Gltf { path: String },
+    /// Two overlapping half transparent triangles, blended over each other
+    Translucent,
+    /// Two opaque triangles crossing each other in depth, which shows what
+    /// the depth test lets through
+    Overlapping,
+}
+
+/// A triangle of translucent geometry, kept around to sort them every frame
+struct Triangle {
+    indices: [u32; 3],
+    /// Middle of its vertices, in model space
+    center: [f32; 3],
+}
+
+/// How the geometry is projected onto the viewport
+#[derive(Clone, Copy)]
+pub enum Projection {
+    /// Seen in perspective from the camera
+    Perspective,
+    /// Mapped onto the viewport pixels, from (0, 0) at its top left corner to
+    /// its size at the bottom right one, ignoring the camera
+    Orthographic,
+}
+
+/// How the vertices of the geometry are assembled into what's rasterized
+#[derive(Clone, Copy)]
+pub enum Topology {
+    /// Every three vertices, or indices, make a filled triangle
+    Triangles,
+    /// A line joins each vertex to the next one, showing the order they're in
+    LineStrip,
+    /// Each vertex is drawn on its own, as a square point
+    Points,
+}
+
+impl Topology {
+    /// The one after this one, in the order they're cycled through
+    pub fn next(self) -> Self {
+        match self {
+            Topology::Triangles => Topology::LineStrip,
+            Topology::LineStrip => Topology::Points,
+            Topology::Points => Topology::Triangles,
+        }
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Topology::Triangles => "triangles",
+            Topology::LineStrip => "a line strip",
+            Topology::Points => "points",
+        }
+    }
+}
+
+/// Where one object of the scene is placed, on top of the model matrix. Each
+/// object is drawn on its own with its matrix pushed along, unlike instances
+/// which are drawn all at once and read their placement from a buffer.
+#[derive(Clone, Copy)]
+pub struct Transform {
+    pub translation: [f32; 3],
+    /// Around the Z axis, in radians
+    pub rotation: f32,
+    /// The same along every axis, so that the normals only need rotating
+    pub scale: f32,
+}
+
+impl Transform {
+    /// Leaves the geometry where the model matrix puts it
+    pub const IDENTITY: Transform = Transform { translation: [0.0; 3], rotation: 0.0, scale: 1.0 };
+
+    fn to_matrix(self) -> [[f32; 4]; 4] {
+        (Matrix4::from_translation(self.translation.into())
+            * Matrix4::from_angle_z(Rad(self.rotation))
+            * Matrix4::from_scale(self.scale))
+            .into()
+    }
+}
+
+/// Where one copy of the geometry is drawn and what it's tinted with, read
+/// once per instance. The color is named apart from the vertices' as vulkano
+/// matches the attributes of both by name.
+#[derive(Default, Copy, Clone)]
+struct InstanceData {
+    offset: [f32; 2],
+    scale: f32,
+    instance_color: [f32; 4],
+}
+vulkano::impl_vertex!(InstanceData, offset, scale, instance_color);
+
+mod vs {
+    vulkano_shaders::shader!{
+        ty: "vertex",
+        path: "src/shaders/triangle.vert"
+    }
+}
+
+mod fs {
+    vulkano_shaders::shader!{
+        ty: "fragment",
+        path: "src/shaders/triangle.frag"
+    }
+}
+
+mod post_vs {
+    vulkano_shaders::shader!{
+        ty: "vertex",
+        path: "src/shaders/post.vert"
+    }
+}
+
+mod normals_vs {
+    vulkano_shaders::shader!{
+        ty: "vertex",
+        path: "src/shaders/normals.vert"
+    }
+}
+
+mod normals_gs {
+    vulkano_shaders::shader!{
+        ty: "geometry",
+        path: "src/shaders/normals.geom"
+    }
+}
+
+mod normals_fs {
+    vulkano_shaders::shader!{
+        ty: "fragment",
+        path: "src/shaders/normals.frag"
+    }
+}
+
+mod post_fs {
+    vulkano_shaders::shader!{
+        ty: "fragment",
+        path: "src/shaders/post.frag"
+    }
+}
+
+mod stencil_mask_vs {
+    vulkano_shaders::shader!{
+        ty: "vertex",
+        path: "src/shaders/stencil_mask.vert"
+    }
+}
+
+mod stencil_mask_fs {
+    vulkano_shaders::shader!{
+        ty: "fragment",
+        path: "src/shaders/stencil_mask.frag"
+    }
+}
+
+mod wave_cs {
+    vulkano_shaders::shader!{
+        ty: "compute",
+        path: "src/shaders/wave.comp"
+    }
+}
+
+/// Image drawn on the quad, among the assets
+const TEXTURE_NAME: &str = "texture.png";
+
+/// Tangent space normal map bumping the lighting of the geometry, among the
+/// assets
+const NORMAL_MAP_NAME: &str = "normal_map.png";
+
+/// Distance of the objects laid out by `make_ring` from the origin
+const RING_RADIUS: f32 = 0.6;
+
+/// Size in pixels the vertices are drawn with as points, and the width lines
+/// start with, when the device supports more than one
+const POINT_SIZE: f32 = 4.0;
+const LINE_WIDTH: f32 = 2.0;
+
+/// Highest mip level sampled from the textures, Vulkan's value for no limit
+const MAX_TEXTURE_LOD: f32 = 1000.0;
+
+/// Monospace bitmap font the text is drawn with, among the assets
+const FONT_NAME: &str = "font.png";
+
+/// Samples per pixel used to anti-alias the edges without `--msaa`: 1
+/// (disabled), 2, 4, 8 or 16. Lowered to the highest count the device
+/// supports if needed.
+const MSAA_SAMPLES: u32 = 4;
+
+/// Width over height of the area drawn into, centered between bars in images
+/// of any other shape so that the scene never gets stretched
+const TARGET_ASPECT: f32 = 16.0 / 9.0;
+
+/// Color of the bars around the `TARGET_ASPECT` area
+const LETTERBOX_COLOR: [f32; 4] = [0.0, 0.0, 0.0, 1.0];
+
+/// Vertices displaced by each work group of the wave compute shader
+const WAVE_GROUP_SIZE: u32 = 64;
+
+/// Direction towards the directional light, from the upper right and in front
+const LIGHT_DIR: [f32; 4] = [0.4, 0.4, 1.0, 0.0];
+const LIGHT_COLOR: [f32; 4] = [1.0, 1.0, 1.0, 1.0];
+
+/// Depth formats with a stencil component, from the most to the least
+/// precise. Devices must support at least one of the first two, any of them
+/// may still not be usable with the sample count chosen.
+const DEPTH_STENCIL_FORMATS: [Format; 3] =
+    [Format::D32Sfloat_S8Uint, Format::D24Unorm_S8Uint, Format::D16Unorm_S8Uint];
+
+/// Triangles of the disc the stencil mask is drawn as, see stencil_mask.vert
+const STENCIL_MASK_SEGMENTS: u32 = 64;
+
+/// Written by the stencil mask, where the geometry is then drawn
+const STENCIL_MASK_REFERENCE: u32 = 1;
+
+/// Parameters a pipeline drawing the geometry is specialized with
+struct Variant {
+    offset_x: f32,
+    /// -1 to mirror the geometry along X, turning its front faces to the back
+    mirror_x: f32,
+    base_color: [f32; 3],
+}
+
+/// Two copies of the quad side by side, each drawn by its own pipeline built
+/// from the same shader modules. The left one is mirrored, wound clockwise,
+/// so that culling the back faces hides it.
+const QUAD_VARIANTS: [Variant; 2] = [
+    Variant { offset_x: -0.6, mirror_x: -1.0, base_color: [1.0, 0.6, 0.6] },
+    Variant { offset_x:  0.6, mirror_x:  1.0, base_color: [0.6, 0.6, 1.0] },
+];
+
+/// The grid and meshes are drawn once as they are
+const SINGLE_VARIANT: [Variant; 1] = [
+    Variant { offset_x: 0.0, mirror_x: 1.0, base_color: [1.0, 1.0, 1.0] },
+];
+
+/// Draws without any vertex buffer, such as the full-screen triangle of the
+/// post-processing pass. Kept as a concrete type since bufferless draws can't
+/// go through the trait object.
+pub type BufferlessPipeline =
+    GraphicsPipeline<
+        BufferlessDefinition,
+        Box<dyn PipelineLayoutAbstract + Send + Sync>,
+        Arc<dyn RenderPassAbstract + Send + Sync>
+    >;
+
+/// One pipeline per variant of the geometry
+type Pipelines = Vec<Arc<dyn GraphicsPipelineAbstract + Send + Sync>>;
+
+/// One draw of the geometry, for any range of its instances. Only refers to
+/// what's `Send + Sync`, so that the threads recording the instances can all
+/// share the same draws.
+struct GeometryDraw<'a> {
+    pipeline: &'a Arc<dyn GraphicsPipelineAbstract + Send + Sync>,
+    set: &'a Arc<dyn DescriptorSet + Send + Sync>,
+    dynamic_state: &'a DynamicState,
+    push_constants: fs::ty::PushConstants,
+}
+
+/// Every pipeline drawing the geometry, one per variant for each way of
+/// drawing it. The topology is fixed when a pipeline is built.
+struct GeometryPipelines {
+    triangles: Pipelines,
+    /// Same as `triangles` but only drawing the edges, `None` when the device
+    /// can't rasterize polygons as lines
+    wireframe: Option<Pipelines>,
+    /// Same as `triangles` but culling the back faces, those wound clockwise
+    /// as seen from the camera
+    culled: Pipelines,
+    line_strip: Pipelines,
+    points: Pipelines,
+}
+
+/// What the geometry's shaders are run from, whether they were compiled along
+/// with the program or at runtime
+type VsEntryPoint<'a> =
+    GraphicsEntryPoint<'a, vs::SpecializationConstants, vs::MainInput, vs::MainOutput, vs::Layout>;
+/// How the pipelines drawing the geometry treat what's already drawn
+#[derive(Clone, Copy)]
+struct PipelineOptions {
+    /// Blended over what's behind
+    translucent: bool,
+    /// Only drawn where the stencil mask was
+    stencil_tested: bool,
+    /// How fragments are tested against the depth already drawn, which flips
+    /// the depth range for the greater comparisons
+    depth_compare: Compare,
+}
+
+type FsEntryPoint<'a> =
+    GraphicsEntryPoint<'a, fs::SpecializationConstants, fs::MainInput, fs::MainOutput, fs::Layout>;
+
+/// Displaces the vertices along a wave, on the GPU
+struct GpuWave {
+    pipeline: Arc<ComputePipeline<PipelineLayout<wave_cs::Layout>>>,
+    /// The vertices displaced from every frame
+    base_vertices: Arc<DeviceLocalBuffer<[Vertex]>>,
+}
+
+/// Signalled once data sent to the GPU is ready to be used
+pub type UploadFuture = Box<dyn GpuFuture + Send + Sync>;
+
+/// A texture being uploaded in the background
+struct TextureReload {
+    texture: Arc<ImmutableImage<Format>>,
+    /// Receives the outcome of the upload once it's done
+    done: Receiver<Result<(), FlushError>>,
+}
+
+/// Everything drawn, independently of where it ends up: a window's swapchain
+/// or an offscreen image
+pub struct Scene {
+    device: Arc<Device>,
+    queue: Arc<Queue>,
+
+    /// Draws the geometry into the offscreen scene image
+    render_pass: Arc<dyn RenderPassAbstract + Send + Sync>,
+    /// Draws the scene image, post-processed, into the target images
+    post_render_pass: Arc<dyn RenderPassAbstract + Send + Sync>,
+    /// One per variant of the geometry and way of drawing it, all sharing the
+    /// same layout
+    pipelines: GeometryPipelines,
+    topology: Topology,
+    /// Only applies to the triangles
+    wireframe: bool,
+    /// Hide the back faces of the triangles, unless drawn as a wireframe
+    culling: bool,
+    /// Width in pixels of the line strip, wireframe and debug lines, set
+    /// through the dynamic state of every draw
+    line_width: f32,
+    /// Only draws the geometry within the center of the scene image
+    masked: bool,
+    /// Spins the geometry through the model matrix, computed on the CPU,
+    /// rather than in the vertex shader
+    spin_on_cpu: bool,
+    /// Draw the vertex normals as lines over the geometry, `None` unless
+    /// they're shown
+    normals_pipelines: Option<Pipelines>,
+    /// Drawn in the second subpass of `render_pass`
+    debug_lines: DebugLines,
+    /// Drawn over the post-processed scene, in the pass of `post_render_pass`
+    text: TextRenderer,
+    /// Drawn behind the geometry, `None` unless there's one
+    skybox: Option<Skybox>,
+    /// Drawn over the geometry, `None` unless there are some
+    particles: Option<Particles>,
+    /// The geometry is drawn once per object, at least one
+    objects: Vec<Transform>,
+    /// Records the first subpass into a secondary command buffer, see
+    /// `enable_secondary_draws`
+    secondary_draws: bool,
+    /// Records the instances in chunks on its threads, `None` to record them
+    /// all on the thread drawing
+    recording_pool: Option<ThreadPool>,
+    /// Counts the scene image by luminance after every frame, `None` unless
+    /// it's enabled
+    histogram: Option<Histogram>,
+    variants: &'static [Variant],
+    /// Kept to rebuild the pipelines when the shaders are reloaded or the
+    /// stencil mask enabled
+    pipeline_options: PipelineOptions,
+    /// Writes the stencil buffer where the geometry is drawn, `None` unless
+    /// it's masked
+    stencil_mask_pipeline: Option<Arc<BufferlessPipeline>>,
+    post_pipeline: Arc<BufferlessPipeline>,
+    /// Reads the scene image pixel per pixel for the post-processing
+    scene_sampler: Arc<Sampler>,
+    /// Radius in pixels of the box blur applied by the post-processing
+    blur_radius: u32,
+    projection: Projection,
+    format: Format,
+    /// Made of depth and stencil when the device allows it
+    depth_format: Format,
+    samples: u32,
+    /// Linear, as the attachments expect it whatever their format
+    clear_color: [f32; 4],
+
+    /// What's in the vertex buffers, minus the GPU wave
+    vertices: Vec<Vertex>,
+    /// Two copies of the vertices, so that the CPU can update one while the
+    /// GPU reads the other. Both start out as the same device local buffer,
+    /// replaced by host visible ones once the CPU updates them.
+    vertex_buffers: [VertexBuffer; 2],
+    /// Which of `vertex_buffers` frames are drawn from
+    current_vertex_buffer: usize,
+    /// `None` unless the vertices are animated on the GPU
+    gpu_wave: Option<GpuWave>,
+    instance_buffer: Arc<CpuAccessibleBuffer<[InstanceData]>>,
+    /// `None` when the vertices are drawn as a plain triangle list, or when
+    /// they're translucent
+    index_buffer: Option<IndexBuffer>,
+    /// Blending only gives the right colors when the farthest triangles are
+    /// drawn first, so the indices of translucent geometry are sorted again
+    /// every frame instead. Instances aren't sorted among each other.
+    translucent_triangles: Option<Vec<Triangle>>,
+    texture: Arc<ImmutableImage<Format>>,
+    normal_map: Arc<ImmutableImage<Format>>,
+    sampler: Arc<Sampler>,
+
+    transfer_queue: Arc<Queue>,
+    /// Where the texture was loaded from, to load it again, `None` when it
+    /// came with the model
+    texture_path: Option<PathBuf>,
+    /// `None` unless the texture is being uploaded again
+    texture_reload: Option<TextureReload>,
+
+    /// Uploads that must be complete before the next frame reads from them. When
+    /// they run on another queue than the graphics one, joining them into the
+    /// frame's future makes vulkano wait on a semaphore between both queues.
+    pending_uploads: Option<UploadFuture>,
+
+    camera_position: [f32; 3],
+    /// Unit vector the camera looks along
+    camera_direction: [f32; 3],
+}
+
+/// The framebuffers drawing into a set of same sized images, along with the
+/// attachments depending on that size. Rebuilt when the size changes.
+pub struct Targets {
+    /// Draws the geometry into the offscreen scene image, which is as big as
+    /// the `TARGET_ASPECT` area of the target images
+    scene_framebuffer: Arc<dyn FramebufferAbstract + Send + Sync>,
+    /// Covers the whole scene image
+    scene_dynamic_state: DynamicState,
+    /// Same viewport, but scissored to the center quarter of the scene image
+    masked_dynamic_state: DynamicState,
+    /// One per target image, post-processing the scene into it
+    framebuffers: Vec<Arc<dyn FramebufferAbstract + Send + Sync>>,
+    /// Gives the post-processing pass access to the scene image
+    post_set: Arc<dyn DescriptorSet + Send + Sync>,
+    /// Letterboxes the post-processing into the `TARGET_ASPECT` area
+    dynamic_state: DynamicState,
+    /// Size of that area, in pixels
+    viewport_dimensions: [u32; 2],
+
+    /// The uniforms of the geometry, written into the next of its slots by
+    /// each frame
+    uniforms: UniformRing<vs::ty::Data>,
+    /// Offset in `uniforms` of those of the frame last drawn
+    uniform_offset: usize,
+    /// Bind each slot of `uniforms` along with the texture and normal map
+    sets: Vec<Arc<dyn DescriptorSet + Send + Sync>>,
+    /// Bind each slot of `uniforms` for the normals pipelines, `None` unless
+    /// the normals were shown when the targets were created
+    normals_sets: Option<Vec<Arc<dyn DescriptorSet + Send + Sync>>>,
+    /// One per target image, `None` unless the histogram was enabled when
+    /// the targets were created
+    histograms: Option<Vec<HistogramTarget>>,
+}
+
+impl Targets {
+    /// Keeps the uniforms of the frame last drawn from being overwritten until
+    /// `fence`, that of the frame, is signaled
+    pub fn track_frame(&mut self, fence: &FrameFence) {
+        self.uniforms.track(self.uniform_offset, fence.clone());
+    }
+
+    /// Leaks the fences of the frames in flight, see `Renderer::abandon`
+    pub fn forget_frames(&mut self) {
+        self.uniforms.forget_fences();
+    }
+}
+
+impl Scene {
+    /// Prepares drawing `geometry` into images of `format`, `instance_count`
+    /// times over the sRGB encoded `clear_color`, anti-aliased with `samples`
+    /// samples per pixel or `MSAA_SAMPLES` when `None`, lowered to what the
+    /// device supports. Images are uploaded through `transfer_queue`.
+    #[allow(clippy::too_many_arguments)]
+    pub fn new(
+        device: Arc<Device>,
+        queue: Arc<Queue>,
+        transfer_queue: Arc<Queue>,
+        format: Format,
+        geometry: Geometry,
+        instance_count: u32,
+        clear_color: [f32; 3],
+        samples: Option<u32>
+    ) -> Result<Self, AppError> {
+        let translucent = matches!(geometry, Geometry::Translucent);
+        // Replaces the texture among the assets
+        let mut model_texture = None;
+        let (mut vertices, indices, variants) = match geometry {
+            Geometry::Grid { vertex_count } => {
+                // 2 triangles, so 6 vertices, per cell of a square-ish grid
+                let cells = (vertex_count / 6).max(1);
+                let rows = (cells as f32).sqrt() as u32;
+                let cols = cells / rows;
+
+                let grid = make_grid(rows, cols);
+                println!("Drawing {} triangles ({} vertices)", grid.len() / 3, grid.len());
+
+                (grid, None, &SINGLE_VARIANT[..])
+            },
+            Geometry::Mesh { path } => {
+                let (vertices, indices) = load_obj(&path)?;
+                let (index_count, index_size) = match &indices {
+                    Indices::U16(indices) => (indices.len(), 16),
+                    Indices::U32(indices) => (indices.len(), 32),
+                };
+                println!(
+                    "Loaded {}: {} vertices, {} triangles, {} bits indices",
+                    path, vertices.len(), index_count / 3, index_size);
+
+                (vertices, Some(indices), &SINGLE_VARIANT[..])
+            },
+            Geometry::Gltf { path } => {
+                let (vertices, indices, base_color) = load_gltf(&path)?;
+                let index_count = match &indices {
+                    Indices::U16(indices) => indices.len(),
+                    Indices::U32(indices) => indices.len(),
+                };
+                println!("Loaded {}: {} vertices, {} triangles", path, vertices.len(), index_count / 3);
+
+                model_texture = base_color;
+                (vertices, Some(indices), &SINGLE_VARIANT[..])
+            },
+            Geometry::Translucent => {
+                let vertex = |position, tex_coord, color| Vertex {
+                    position,
+                    tex_coord,
+                    color,
+                    normal: [0.0, 0.0, 1.0],
+                    tangent: [0.0; 3],
+                };
+
+                // Where they overlap, red over blue over the clear color C gives
+                // 0.5 * red + 0.25 * blue + 0.25 * C, before the texture's tint
+                let red = [1.0, 0.0, 0.0, 0.5];
+                let blue = [0.0, 0.0, 1.0, 0.5];
+                let vertices = vec![
+                    vertex([-0.6, -0.4,  0.1], [0.0, 1.0], red),
+                    vertex([ 0.3, -0.4,  0.1], [1.0, 1.0], red),
+                    vertex([-0.15, 0.5,  0.1], [0.5, 0.0], red),
+                    vertex([-0.3, -0.5, -0.1], [0.0, 1.0], blue),
+                    vertex([ 0.6, -0.5, -0.1], [1.0, 1.0], blue),
+                    vertex([ 0.15, 0.4, -0.1], [0.5, 0.0], blue),
+                ];
+
+                (vertices, None, &SINGLE_VARIANT[..])
+            },
+            Geometry::Overlapping => {
+                let vertex = |position, tex_coord, color| Vertex {
+                    position,
+                    tex_coord,
+                    color,
+                    normal: [0.0, 0.0, 1.0],
+                    tangent: [0.0; 3],
+                };
+
+                // Leaning opposite ways, each is in front on one side of where
+                // they cross. The blue one is drawn last, and covers the red one
+                // everywhere once the depth test always passes.
+                let red = [1.0, 0.0, 0.0, 1.0];
+                let blue = [0.0, 0.0, 1.0, 1.0];
+                let vertices = vec![
+                    vertex([-0.6, -0.4,  0.3], [0.0, 1.0], red),
+                    vertex([ 0.6, -0.4, -0.3], [1.0, 1.0], red),
+                    vertex([ 0.0,  0.5,  0.0], [0.5, 0.0], red),
+                    vertex([-0.6, -0.5, -0.3], [0.0, 1.0], blue),
+                    vertex([ 0.6, -0.5,  0.3], [1.0, 1.0], blue),
+                    vertex([ 0.0,  0.4,  0.0], [0.5, 0.0], blue),
+                ];
+
+                (vertices, None, &SINGLE_VARIANT[..])
+            },
+            Geometry::Quad => {
+                let vertex = |position: [f32; 2], tex_coord, color| Vertex {
+                    position: [position[0], position[1], 0.0],
+                    tex_coord,
+                    color,
+                    normal: [0.0, 0.0, 1.0],
+                    tangent: [0.0; 3],
+                };
+
+                // A quad: 4 shared vertices instead of the 6 two separate triangles need
+                // Y points up, while images are stored top row first
+                // Red, green and blue corners blend across the first triangle
+                let vertex1 = vertex([-0.5, -0.5], [0.0, 1.0], [1.0, 0.0, 0.0, 1.0]);
+                let vertex2 = vertex([ 0.5, -0.5], [1.0, 1.0], [0.0, 1.0, 0.0, 1.0]);
+                let vertex3 = vertex([ 0.5,  0.5], [1.0, 0.0], [0.0, 0.0, 1.0, 1.0]);
+                let vertex4 = vertex([-0.5,  0.5], [0.0, 0.0], [1.0, 1.0, 1.0, 1.0]);
+
+                let indices: Vec<u16> = vec![
+                    0, 1, 2,
+                    2, 3, 0,
+                ];
+
+                (
+                    vec![vertex1, vertex2, vertex3, vertex4],
+                    Some(Indices::U16(indices)),
+                    &QUAD_VARIANTS[..]
+                )
+            },
+        };
+
+        // None of the geometry comes with tangents, OBJ files can't store them
+        let triangles: Vec<[usize; 3]> = match &indices {
+            Some(Indices::U16(indices)) =>
+                indices.chunks(3).map(|t| [t[0] as usize, t[1] as usize, t[2] as usize]).collect(),
+            Some(Indices::U32(indices)) =>
+                indices.chunks(3).map(|t| [t[0] as usize, t[1] as usize, t[2] as usize]).collect(),
+            None => (0 .. vertices.len() / 3).map(|i| [3 * i, 3 * i + 1, 3 * i + 2]).collect(),
+        };
+        compute_tangents(&mut vertices, &triangles);
+
+        let translucent_triangles =
+            if translucent {
+                Some(vertices.chunks(3).enumerate().map(|(i, triangle)| {
+                    let first_index = 3 * i as u32;
+                    let mut center = [0.0; 3];
+                    for vertex in triangle {
+                        for (center, position) in center.iter_mut().zip(&vertex.position) {
+                            *center += position / 3.0;
+                        }
+                    }
+
+                    Triangle { indices: [first_index, first_index + 1, first_index + 2], center }
+                }).collect())
+            } else {
+                None
+            };
+
+        // Also a storage buffer for the GPU wave to write into
+        let vertex_buffer =
+            VertexBuffer::DeviceLocal(upload_to_device_local(
+                &device, &queue, &vertices,
+                BufferUsage { vertex_buffer: true, storage_buffer: true, .. BufferUsage::none() }
+            )?);
+        let vertex_buffers = [vertex_buffer.clone(), vertex_buffer];
+
+        let instances = make_instances(instance_count);
+        if instances.len() > 1 {
+            println!("Drawing {} instances", instances.len());
+        }
+
+        let instance_buffer =
+            CpuAccessibleBuffer::from_iter(
+                device.clone(), BufferUsage::vertex_buffer(),
+                instances.into_iter()
+            )?;
+
+        let index_buffer = match indices {
+            Some(Indices::U16(indices)) =>
+                Some(IndexBuffer::U16(
+                    upload_to_device_local(&device, &queue, &indices, BufferUsage::index_buffer())?
+                )),
+            Some(Indices::U32(indices)) =>
+                Some(IndexBuffer::U32(
+                    upload_to_device_local(&device, &queue, &indices, BufferUsage::index_buffer())?
+                )),
+            None => None,
+        };
+
+        // The vertices and indices above are already copied from their staging
+        // buffers, waited for on the graphics queue. The textures are uploaded
+        // through the transfer queue, which the first frame waits for.
+        let assets = Assets::new();
+        let mut model_upload = None;
+        let (texture, texture_path) = match model_texture {
+            Some(image) => {
+                let (texture, upload) =
+                    assets::upload_pixels(transfer_queue.clone(), image, Format::R8G8B8A8Srgb)?;
+                model_upload = Some(upload);
+                (texture, None)
+            },
+            None => {
+                let texture = assets.load_texture(transfer_queue.clone(), TEXTURE_NAME)?;
+                (texture, Some(assets.path(TEXTURE_NAME)))
+            },
+        };
+        let normal_map = assets.load_normal_map(transfer_queue.clone(), NORMAL_MAP_NAME)?;
+        let font = assets.load_texture(transfer_queue.clone(), FONT_NAME)?;
+
+        let sampler =
+            Sampler::new(
+                device.clone(),
+                Filter::Linear, Filter::Linear, MipmapMode::Nearest,
+                SamplerAddressMode::Repeat, SamplerAddressMode::Repeat, SamplerAddressMode::Repeat,
+                0.0, 1.0, 0.0, 0.0
+            )?;
+
+        // The render pass, its attachments and the pipelines are all built
+        // with that count
+        let samples = choose_sample_count(&device, format, samples.unwrap_or(MSAA_SAMPLES));
+        let depth_format = choose_depth_format(&device, samples);
+
+        let vs = vs::Shader::load(device.clone())?;
+        let fs = fs::Shader::load(device.clone())?;
+        let post_vs = post_vs::Shader::load(device.clone())?;
+        let post_fs = post_fs::Shader::load(device.clone())?;
+
+        // The geometry is drawn into an offscreen image first, which a second
+        // render pass then samples to write the post-processed result into the
+        // target image. An input attachment read within the same render pass
+        // would be cheaper, but only gives access to the fragment's own pixel,
+        // not the ones around it a blur needs.
+        let render_pass =
+            Arc::new(
+                vulkano::ordered_passes_renderpass!(
+                    device.clone(),
+                    attachments: {
+                        // Multisampled image the scene is drawn into
+                        intermediary: {
+                            load: Clear,
+                            store: DontCare,
+                            format: format,
+                            samples: samples,
+                        },
+                        // Scene once its samples are resolved, kept for the
+                        // post-processing pass
+                        scene: {
+                            load: DontCare,
+                            store: Store,
+                            format: format,
+                            samples: 1,
+                        },
+                        depth: {
+                            load: Clear,
+                            store: DontCare,
+                            format: depth_format,
+                            samples: samples,
+                        }
+                    },
+                    passes: [
+                        {
+                            color: [intermediary],
+                            depth_stencil: {depth},
+                            input: [],
+                            resolve: []
+                        },
+                        // The debug lines, drawn over everything
+                        {
+                            color: [intermediary],
+                            depth_stencil: {},
+                            input: [],
+                            resolve: [scene]
+                        }
+                    ]
+                )?
+            ) as Arc<dyn RenderPassAbstract + Send + Sync>;
+
+        let post_render_pass =
+            Arc::new(
+                vulkano::single_pass_renderpass!(
+                    device.clone(),
+                    attachments: {
+                        // Swapchain or offscreen image, cleared for the letterbox bars
+                        color: {
+                            load: Clear,
+                            store: Store,
+                            format: format,
+                            samples: 1,
+                        }
+                    },
+                    pass: {
+                        color: [color],
+                        depth_stencil: {}
+                    }
+                )?
+            ) as Arc<dyn RenderPassAbstract + Send + Sync>;
+
+        let pipeline_options =
+            PipelineOptions { translucent, stencil_tested: false, depth_compare: Compare::Less };
+        let pipelines =
+            create_pipelines(
+                &device, vs.main_entry_point(), fs.main_entry_point(), &render_pass, variants,
+                pipeline_options
+            )?;
+
+        let debug_lines = DebugLines::new(device.clone(), &render_pass, 1)?;
+        let text = TextRenderer::new(device.clone(), &post_render_pass, 0, font)?;
+
+        let post_pipeline =
+            Arc::new(
+                GraphicsPipeline::start()
+                    .vertex_shader(post_vs.main_entry_point(), ())
+                    .viewports_scissors_dynamic(1)
+                    .fragment_shader(post_fs.main_entry_point(), ())
+                    .render_pass(
+                        Subpass::from(post_render_pass.clone(), 0).ok_or(AppError::MissingSubpass(0))?
+                    )
+                    .build(device.clone())?
+            );
+
+        // The scene image is exactly as big as the area it's drawn back into,
+        // each fragment reads the center of its own pixel
+        let line_width = clamp_line_width(&device, LINE_WIDTH);
+
+        let scene_sampler =
+            Sampler::new(
+                device.clone(),
+                Filter::Nearest, Filter::Nearest, MipmapMode::Nearest,
+                SamplerAddressMode::ClampToEdge, SamplerAddressMode::ClampToEdge,
+                SamplerAddressMode::ClampToEdge,
+                0.0, 1.0, 0.0, 0.0
+            )?;
+
+        Ok(Scene {
+            device,
+            queue,
+
+            render_pass,
+            post_render_pass,
+            pipelines,
+            topology: Topology::Triangles,
+            wireframe: false,
+            culling: false,
+            line_width,
+            masked: false,
+            spin_on_cpu: false,
+            normals_pipelines: None,
+            debug_lines,
+            text,
+            skybox: None,
+            particles: None,
+            objects: vec![Transform::IDENTITY],
+            secondary_draws: false,
+            recording_pool: None,
+            histogram: None,
+            variants,
+            pipeline_options,
+            stencil_mask_pipeline: None,
+            post_pipeline,
+            scene_sampler,
+            blur_radius: 0,
+            projection: Projection::Perspective,
+            format,
+            depth_format,
+            samples,
+            clear_color: srgb_to_linear(clear_color),
+
+            vertices,
+            vertex_buffers,
+            current_vertex_buffer: 0,
+            gpu_wave: None,
+            instance_buffer,
+            index_buffer,
+            translucent_triangles,
+            texture,
+            normal_map,
+            sampler,
+
+            transfer_queue,
+            texture_path,
+            texture_reload: None,
+
+            pending_uploads: match (assets.take_pending_uploads(), model_upload) {
+                (Some(assets_upload), Some(model_upload)) => Some(Box::new(assets_upload.join(model_upload))),
+                (assets_upload, model_upload) => assets_upload.or(model_upload),
+            },
+
+            camera_position: [0.0, 0.0, 2.0],
+            camera_direction: [0.0, 0.0, -1.0],
+        })
+    }
+
+    /// Prepares drawing the scene `config` describes into images of `format`,
+    /// the same way in every mode drawing it
+    pub fn from_config(
+        device: Arc<Device>,
+        queue: Arc<Queue>,
+        transfer_queue: Arc<Queue>,
+        format: Format,
+        config: &Config
+    ) -> Result<Self, AppError> {
+        let mut scene =
+            Scene::new(
+                device, queue, transfer_queue, format,
+                config.geometry.clone(), config.instance_count, config.clear_color, config.msaa_samples
+            )?;
+        scene.set_depth_compare(config.depth_compare)?;
+        if config.show_normals {
+            scene.show_normals()?;
+        }
+        if config.gpu_wave {
+            scene.animate_on_gpu()?;
+        }
+        if config.rainbow {
+            scene.randomize_instance_colors()?;
+        }
+        scene.set_blur_radius(config.blur_radius);
+        if config.ortho {
+            scene.set_projection(Projection::Orthographic);
+        }
+        if config.stencil {
+            scene.enable_stencil_mask()?;
+        }
+        if let Some(face_paths) = &config.skybox {
+            scene.set_skybox(face_paths)?;
+        }
+        if let Some(object_count) = config.object_count {
+            scene.set_objects(make_ring(object_count));
+        }
+        if let Some(particle_count) = config.particle_count {
+            scene.add_particles(particle_count)?;
+        }
+        if let Some(anisotropy) = config.anisotropy {
+            scene.set_anisotropy(anisotropy)?;
+        }
+        if config.secondary {
+            scene.enable_secondary_draws();
+        }
+        if let Some(thread_count) = config.record_threads {
+            scene.enable_parallel_recording(thread_count)?;
+        }
+        if config.histogram {
+            scene.enable_histogram()?;
+        }
+
+        Ok(scene)
+    }
+
+    /// Builds one framebuffer per image in `images`, which must all have the
+    /// scene's format and the same size. Called once during initialization,
+    /// then again whenever the images are resized.
+    pub fn create_targets<I>(&self, images: &[Arc<I>]) -> Result<Targets, AppError>
+        where I: ImageViewAccess + Send + Sync + 'static
+    {
+        let dimensions = images[0].dimensions().width_height();
+
+        // The widest area of `TARGET_ASPECT` fitting in the images, centered.
+        // The bars around are left with the color the target is cleared to.
+        let [width, height] = dimensions;
+        let viewport_dimensions =
+            if width as f32 / height as f32 > TARGET_ASPECT {
+                [((height as f32 * TARGET_ASPECT).round() as u32).max(1), height]
+            } else {
+                [width, ((width as f32 / TARGET_ASPECT).round() as u32).max(1)]
+            };
+        let origin = [(width - viewport_dimensions[0]) / 2, (height - viewport_dimensions[1]) / 2];
+
+        let dynamic_state =
+            DynamicState {
+                viewports: Some(vec![Viewport {
+                    origin: [origin[0] as f32, origin[1] as f32],
+                    dimensions: [viewport_dimensions[0] as f32, viewport_dimensions[1] as f32],
+                    depth_range: 0.0 .. 1.0,
+                }]),
+                // Nothing is drawn outside the viewport either, lines and points
+                // included, which could otherwise stick out of it
+                scissors: Some(vec![Scissor {
+                    origin: [origin[0] as i32, origin[1] as i32],
+                    dimensions: viewport_dimensions,
+                }]),
+                .. DynamicState::none()
+            };
+
+        // The scene is only drawn within that area, so the images it's drawn
+        // into are just as big. They're recreated along with the framebuffers.
+        let intermediary =
+            AttachmentImage::transient_multisampled(
+                self.device.clone(), viewport_dimensions, self.samples, self.format
+            )?;
+        let scene = AttachmentImage::sampled(self.device.clone(), viewport_dimensions, self.format)?;
+        let depth_buffer =
+            AttachmentImage::transient_multisampled(
+                self.device.clone(), viewport_dimensions, self.samples, self.depth_format
+            )?;
+
+        let scene_framebuffer =
+            Arc::new(
+                Framebuffer::start(self.render_pass.clone())
+                    .add(intermediary)?
+                    .add(scene.clone())?
+                    .add(depth_buffer)?
+                    .build()?
+            ) as Arc<dyn FramebufferAbstract + Send + Sync>;
+
+        let scene_dynamic_state =
+            DynamicState {
+                viewports: Some(vec![Viewport {
+                    origin: [0.0, 0.0],
+                    dimensions: [viewport_dimensions[0] as f32, viewport_dimensions[1] as f32],
+                    depth_range: 0.0 .. 1.0,
+                }]),
+                scissors: Some(vec![Scissor {
+                    origin: [0, 0],
+                    dimensions: viewport_dimensions,
+                }]),
+                .. DynamicState::none()
+            };
+
+        // Half as wide and high, the geometry outside isn't even rasterized and
+        // the clear color shows through instead
+        let masked_dynamic_state =
+            DynamicState {
+                scissors: Some(vec![Scissor {
+                    origin: [(viewport_dimensions[0] / 4) as i32, (viewport_dimensions[1] / 4) as i32],
+                    dimensions: [(viewport_dimensions[0] / 2).max(1), (viewport_dimensions[1] / 2).max(1)],
+                }]),
+                .. scene_dynamic_state.clone()
+            };
+
+        let framebuffers =
+            images.iter().map(|image| {
+                Ok(Arc::new(
+                    Framebuffer::start(self.post_render_pass.clone())
+                        .add(image.clone())?
+                        .build()?
+                ) as Arc<dyn FramebufferAbstract + Send + Sync>)
+            }).collect::<Result<Vec<_>, AppError>>()?;
+
+        let post_set =
+            Arc::new(
+                PersistentDescriptorSet::start(self.post_pipeline.clone(), 0)
+                    .add_sampled_image(scene.clone(), self.scene_sampler.clone())?
+                    .build()?
+            );
+
+        // Built once here rather than every frame, which used to allocate a
+        // uniform buffer and one or two descriptor sets per frame. The uniforms
+        // only change in content, so the sets can point to the same slots all
+        // along. They're only built again when the texture is reloaded, by
+        // `rebind_texture`. A slot per image is as many frames as can be in
+        // flight, the ring only waits when they come back around faster. The
+        // wave's set, with `--gpu-wave`, is still built every frame by `draw`.
+        let uniforms = UniformRing::new(self.device.clone(), images.len())?;
+
+        let sets = self.build_sets(&uniforms)?;
+
+        let normals_sets = match &self.normals_pipelines {
+            Some(normals_pipelines) =>
+                Some(uniforms.slots().into_iter().map(|slot| {
+                    Ok(Arc::new(
+                        PersistentDescriptorSet::start(normals_pipelines[0].clone(), 0)
+                            .add_buffer(slot)?
+                            .build()?
+                    ) as Arc<dyn DescriptorSet + Send + Sync>)
+                }).collect::<Result<Vec<_>, AppError>>()?),
+            None => None,
+        };
+
+        let histograms = match &self.histogram {
+            Some(histogram) =>
+                Some(images.iter().map(|_| histogram.create_target(scene.clone(), viewport_dimensions))
+                    .collect::<Result<Vec<_>, _>>()?),
+            None => None,
+        };
+
+        Ok(Targets {
+            scene_framebuffer,
+            scene_dynamic_state,
+            masked_dynamic_state,
+            framebuffers,
+            post_set,
+            dynamic_state,
+            viewport_dimensions,
+
+            uniforms,
+            uniform_offset: 0,
+            sets,
+            normals_sets,
+            histograms,
+        })
+    }
+
+    /// Blurs the scene by averaging the pixels up to `radius` pixels away
+    /// from each one, from the next frame on. 0 turns the blur off.
+    pub fn set_blur_radius(&mut self, radius: u32) {
+        self.blur_radius = radius;
+    }
+
+    /// Tints every instance with a hue of its own picked at random, rather
+    /// than along the gradient across the grid, from the next frame on
+    pub fn randomize_instance_colors(&mut self) -> Result<(), AppError> {
+        // Differs from one run to the next, no need for anything stronger
+        let seed = SystemTime::now().duration_since(UNIX_EPOCH).map_or(0, |time| time.subsec_nanos());
+
+        for (index, instance) in self.instance_buffer.write()?.iter_mut().enumerate() {
+            instance.instance_color = hue_to_rgba(random_fraction(seed, index as u32));
+        }
+
+        Ok(())
+    }
+
+    /// Tests the depth of the geometry and its normals with `compare`, as
+    /// reverse-Z does with the greater comparisons. Rebuilds their pipelines
+    /// from the shaders compiled along with the program, into targets created
+    /// afterwards.
+    pub fn set_depth_compare(&mut self, compare: Compare) -> Result<(), AppError> {
+        if compare == self.pipeline_options.depth_compare {
+            return Ok(());
+        }
+
+        self.pipeline_options.depth_compare = compare;
+        let vs = vs::Shader::load(self.device.clone())?;
+        let fs = fs::Shader::load(self.device.clone())?;
+        self.pipelines =
+            create_pipelines(
+                &self.device, vs.main_entry_point(), fs.main_entry_point(), &self.render_pass,
+                self.variants, self.pipeline_options
+            )?;
+        if self.normals_pipelines.is_some() {
+            self.show_normals()?;
+        }
+
+        Ok(())
+    }
+
+    /// Projects the geometry with `projection` from the next frame on
+    pub fn set_projection(&mut self, projection: Projection) {
+        self.projection = projection;
+    }
+
+    /// Switches to the next topology the vertices are drawn with, among the
+    /// ones built up front, returning it
+    pub fn cycle_topology(&mut self) -> Topology {
+        self.topology = self.topology.next();
+        self.topology
+    }
+
+    /// Switches between drawing filled polygons and only their edges
+    pub fn toggle_wireframe(&mut self) {
+        if self.pipelines.wireframe.is_some() {
+            self.wireframe = !self.wireframe;
+        } else {
+            println!("Warning: wireframe mode is not supported by this device");
+        }
+    }
+
+    /// Switches between drawing both faces of the triangles and only the
+    /// front ones, returning whether the back ones are now culled
+    pub fn toggle_culling(&mut self) -> bool {
+        self.culling = !self.culling;
+        self.culling
+    }
+
+    /// Widens the lines by `delta` pixels from the next frame on, or narrows
+    /// them when negative, within what the device supports. Returns the new
+    /// width.
+    pub fn change_line_width(&mut self, delta: f32) -> f32 {
+        if !self.device.enabled_features().wide_lines {
+            println!("Warning: wide lines are not supported by this device");
+        }
+
+        self.line_width = clamp_line_width(&self.device, self.line_width + delta);
+        self.line_width
+    }
+
+    /// Loads the texture again from its file and starts uploading it through
+    /// the transfer queue, which has a lower priority than the graphics one.
+    /// The upload is submitted on its own rather than along with a frame, so
+    /// frames keep sampling the current texture meanwhile, until
+    /// `poll_texture_reload` swaps it in. Does nothing while one is running.
+    pub fn reload_texture(&mut self) -> Result<(), AppError> {
+        if self.texture_reload.is_some() {
+            return Ok(());
+        }
+
+        let texture_path = match &self.texture_path {
+            Some(texture_path) => texture_path,
+            None => {
+                println!("Warning: the texture came with the model, it can't be reloaded");
+                return Ok(());
+            },
+        };
+
+        let (texture, upload) =
+            assets::upload_image(self.transfer_queue.clone(), texture_path, Format::R8G8B8A8Srgb)?;
+        let fence = upload.then_signal_fence_and_flush()?;
+
+        // Waiting on a fence with a timeout forgets what it waited for, even
+        // when it times out, so it can't be polled. A thread waits instead.
+        let (sender, done) = mpsc::channel();
+        thread::spawn(move || {
+            let _ = sender.send(fence.wait(None));
+        });
+
+        self.texture_reload = Some(TextureReload { texture, done });
+
+        Ok(())
+    }
+
+    /// Draws the texture `reload_texture` uploaded from now on if the upload
+    /// is done, returning whether it was. The sets of existing targets still
+    /// bind the previous one until `rebind_texture` is called on them.
+    pub fn poll_texture_reload(&mut self) -> Result<bool, AppError> {
+        let reload = match self.texture_reload.take() {
+            Some(reload) => reload,
+            None => return Ok(false),
+        };
+
+        match reload.done.try_recv() {
+            Ok(result) => {
+                result?;
+                self.texture = reload.texture;
+                Ok(true)
+            },
+            Err(TryRecvError::Empty) => {
+                self.texture_reload = Some(reload);
+                Ok(false)
+            },
+            // The waiting thread panicked, the upload is given up on
+            Err(TryRecvError::Disconnected) => Ok(false),
+        }
+    }
+
+    /// Rebuilds the sets of `targets` to bind the current texture
+    pub fn rebind_texture(&self, targets: &mut Targets) -> Result<(), AppError> {
+        targets.sets = self.build_sets(&targets.uniforms)?;
+
+        Ok(())
+    }
+
+    /// Binds each slot of `uniforms` along with the texture and normal map.
+    /// All the pipelines of a pass share the same layout, so the sets work for
+    /// any of them, including those rebuilt later on.
+    fn build_sets(&self, uniforms: &UniformRing<vs::ty::Data>)
+        -> Result<Vec<Arc<dyn DescriptorSet + Send + Sync>>, AppError>
+    {
+        uniforms.slots().into_iter().map(|slot| {
+            Ok(Arc::new(
+                PersistentDescriptorSet::start(self.pipelines.triangles[0].clone(), 0)
+                    .add_buffer(slot)?
+                    .add_sampled_image(self.texture.clone(), self.sampler.clone())?
+                    .add_sampled_image(self.normal_map.clone(), self.sampler.clone())?
+                    .build()?
+            ) as Arc<dyn DescriptorSet + Send + Sync>)
+        }).collect()
+    }
+
+    /// Surrounds the scene with the cubemap loaded from the six PNGs at
+    /// `face_paths`, in the order of `skybox::FACE_NAMES`. Only drawn with the
+    /// perspective projection, the orthographic one has no view direction.
+    pub fn set_skybox(&mut self, face_paths: &[String]) -> Result<(), AppError> {
+        let (skybox, upload) =
+            Skybox::new(self.device.clone(), self.transfer_queue.clone(), &self.render_pass, 0, face_paths)?;
+
+        self.skybox = Some(skybox);
+        self.pending_uploads = Some(match self.pending_uploads.take() {
+            Some(previous) => Box::new(previous.join(upload)),
+            None => upload,
+        });
+
+        Ok(())
+    }
+
+    /// Filters the texture and normal map anisotropically from now on, over at
+    /// most `anisotropy` samples or as many as the device allows. Falls back
+    /// to trilinear filtering without the `sampler_anisotropy` feature. Only
+    /// into targets created afterwards.
+    pub fn set_anisotropy(&mut self, anisotropy: u32) -> Result<(), AppError> {
+        let max_anisotropy =
+            if self.device.enabled_features().sampler_anisotropy {
+                let limit = self.device.physical_device().limits().max_sampler_anisotropy();
+                if anisotropy as f32 > limit {
+                    println!("Warning: the device filters over at most {} samples", limit);
+                }
+
+                (anisotropy as f32).min(limit).max(1.0)
+            } else {
+                1.0
+            };
+
+        // The textures only have a single mip level for now, every level
+        // generated later on is sampled
+        self.sampler =
+            Sampler::new(
+                self.device.clone(),
+                Filter::Linear, Filter::Linear, MipmapMode::Linear,
+                SamplerAddressMode::Repeat, SamplerAddressMode::Repeat, SamplerAddressMode::Repeat,
+                0.0, max_anisotropy, 0.0, MAX_TEXTURE_LOD
+            )?;
+
+        Ok(())
+    }
+
+    /// Draws the geometry once per transform of `objects` from the next frame
+    /// on, each with a draw of its own. Translucent triangles are only sorted
+    /// within each object.
+    pub fn set_objects(&mut self, objects: Vec<Transform>) {
+        assert!(!objects.is_empty(), "the scene has at least one object");
+        self.objects = objects;
+    }
+
+    /// Records the geometry into a secondary command buffer inheriting the
+    /// first subpass from now on, which the frame's primary command buffer
+    /// then executes, rather than recording it inline.
+    ///
+    /// This is the split that scales with the number of objects: each worker
+    /// thread records the draws of its share of them into a secondary command
+    /// buffer of its own, from a pool of its own, while the main thread only
+    /// begins the render pass and executes them all, in order. With a single
+    /// secondary command buffer recorded on the same thread, as here, there's
+    /// nothing to gain but the demonstration.
+    ///
+    /// vulkano 0.13 doesn't track the accesses of secondary command buffers:
+    /// it inserts no barrier before them, nor keeps the CPU from writing what
+    /// they read. The geometry then stays inline whenever something else
+    /// writes what it reads, the vertices animated on the GPU or the CPU and
+    /// the particles, which share its subpass.
+    pub fn enable_secondary_draws(&mut self) {
+        self.secondary_draws = true;
+    }
+
+    /// Splits the instances into as many chunks as `thread_count`, recorded
+    /// at the same time on a pool of as many threads, each chunk into a
+    /// secondary command buffer of its own, from now on. The frame then
+    /// executes them all in order, after the one of what's drawn behind the
+    /// geometry. Enables the secondary draws too, and falls back to drawing
+    /// inline along with them.
+    ///
+    /// The draws only share `Arc`s of pipelines, descriptor sets and buffers,
+    /// which vulkano makes `Send + Sync`. Command pools aren't: a Vulkan pool
+    /// and what's allocated from it must only ever be used by one thread at a
+    /// time. The standard pool of the device keeps one pool per thread
+    /// allocating from it for that reason, each worker then records into a
+    /// pool no other thread touches. Command buffers are only given back to
+    /// their pool when dropped, through a queue safe to push into from any
+    /// thread, once the frame executing them is done.
+    pub fn enable_parallel_recording(&mut self, thread_count: u32) -> Result<(), AppError> {
+        let pool =
+            ThreadPoolBuilder::new()
+                .num_threads(thread_count as usize)
+                .thread_name(|index| format!("recording-{}", index))
+                .build()?;
+
+        self.recording_pool = Some(pool);
+        self.secondary_draws = true;
+        Ok(())
+    }
+
+    /// Records `draws` on the threads of `pool`, each for a chunk of the
+    /// instances and into a secondary command buffer inheriting `subpass`,
+    /// returned in the order of the instances
+    fn record_instance_chunks(
+        &self,
+        pool: &ThreadPool,
+        subpass: &Subpass<Arc<dyn RenderPassAbstract + Send + Sync>>,
+        draws: &[GeometryDraw],
+        vertex_buffer: &Arc<dyn BufferAccess + Send + Sync>,
+        index_buffer: Option<&IndexBuffer>
+    ) -> Result<Vec<AutoCommandBuffer>, AppError> {
+        // Only what the workers share, the scene itself isn't `Sync`
+        let device = &self.device;
+        let queue_family = self.queue.family();
+        let instance_buffer = &self.instance_buffer;
+        let chunks = split_evenly(instance_buffer.len(), pool.current_num_threads());
+
+        pool.install(|| {
+            chunks.into_par_iter()
+                .map(|chunk| {
+                    let instances =
+                        Arc::new(
+                            instance_buffer.clone().into_buffer_slice()
+                                .slice(chunk)
+                                .expect("every chunk is within the instances")
+                        ) as Arc<dyn BufferAccess + Send + Sync>;
+
+                    // Allocated from the pool of the worker's own thread
+                    let builder =
+                        AutoCommandBufferBuilder::secondary_graphics_one_time_submit(
+                            device.clone(), queue_family, subpass.clone()
+                        )?;
+                    Ok(record_instances(builder, draws, vertex_buffer, &instances, index_buffer)?.build()?)
+                })
+                .collect()
+        })
+    }
+
+    /// Whether the geometry goes into a secondary command buffer this frame,
+    /// only when nothing writes what it reads, see `enable_secondary_draws`
+    fn records_on_secondary(&self) -> bool {
+        let vertices_written =
+            self.gpu_wave.is_some()
+                || self.vertex_buffers.iter().any(|buffer| matches!(buffer, VertexBuffer::HostVisible(_)));
+        self.secondary_draws && !vertices_written && self.particles.is_none()
+    }
+
+    /// Simulates `count` particles on the GPU from now on, bouncing around the
+    /// scene over the geometry
+    pub fn add_particles(&mut self, count: u32) -> Result<(), AppError> {
+        if !self.queue.family().supports_compute() {
+            println!("Warning: the graphics queue doesn't support compute, the particles are disabled");
+            return Ok(());
+        }
+
+        self.particles = Some(Particles::new(self.device.clone(), &self.queue, &self.render_pass, 0, count)?);
+
+        Ok(())
+    }
+
+    /// Advances what the scene simulates, the particles, by `delta_time`
+    /// seconds, shown from the next frame drawn on. Animations only follow the
+    /// time each frame is drawn at.
+    pub fn update(&self, delta_time: f32) {
+        if let Some(particles) = &self.particles {
+            particles.advance(delta_time);
+        }
+    }
+
+    /// Switches between spinning the geometry on the CPU, uploading its model
+    /// matrix every frame, and in the vertex shader from the pushed time.
+    /// Returns whether it's now spun on the CPU.
+    pub fn toggle_spin_on_cpu(&mut self) -> bool {
+        self.spin_on_cpu = !self.spin_on_cpu;
+        self.spin_on_cpu
+    }
+
+    /// Switches between drawing the whole scene and only its center quarter
+    pub fn toggle_mask(&mut self) {
+        self.masked = !self.masked;
+    }
+
+    /// Only draws the geometry within a disc in the middle of the scene from
+    /// now on, drawn into the stencil buffer before it. Needs a depth format
+    /// with a stencil component.
+    pub fn enable_stencil_mask(&mut self) -> Result<(), AppError> {
+        if self.depth_format.ty() != FormatTy::DepthStencil {
+            println!("Warning: the depth buffer has no stencil, the geometry can't be masked");
+            return Ok(());
+        }
+
+        let vs = stencil_mask_vs::Shader::load(self.device.clone())?;
+        let fs = stencil_mask_fs::Shader::load(self.device.clone())?;
+
+        // Stencil written wherever the disc is, nothing else
+        let stencil = Stencil {
+            compare: Compare::Always,
+            pass_op: StencilOp::Replace,
+            fail_op: StencilOp::Keep,
+            depth_fail_op: StencilOp::Keep,
+            compare_mask: Some(0xff),
+            write_mask: Some(0xff),
+            reference: Some(STENCIL_MASK_REFERENCE),
+        };
+        let depth_stencil = DepthStencil {
+            stencil_front: stencil,
+            stencil_back: stencil,
+            .. DepthStencil::disabled()
+        };
+
+        let stencil_mask_pipeline =
+            Arc::new(
+                GraphicsPipeline::start()
+                    .vertex_shader(vs.main_entry_point(), ())
+                    .viewports_scissors_dynamic(1)
+                    .fragment_shader(fs.main_entry_point(), ())
+                    .depth_stencil(depth_stencil)
+                    .blend_collective(AttachmentBlend {
+                        mask_red: false,
+                        mask_green: false,
+                        mask_blue: false,
+                        mask_alpha: false,
+                        .. AttachmentBlend::pass_through()
+                    })
+                    .render_pass(
+                        Subpass::from(self.render_pass.clone(), 0).ok_or(AppError::MissingSubpass(0))?
+                    )
+                    .build(self.device.clone())?
+            );
+
+        // Built from the shaders compiled along with the program, dropping the
+        // ones reloaded so far
+        self.pipeline_options.stencil_tested = true;
+        let vs = vs::Shader::load(self.device.clone())?;
+        let fs = fs::Shader::load(self.device.clone())?;
+        self.pipelines =
+            create_pipelines(
+                &self.device, vs.main_entry_point(), fs.main_entry_point(), &self.render_pass,
+                self.variants, self.pipeline_options
+            )?;
+        self.stencil_mask_pipeline = Some(stencil_mask_pipeline);
+
+        Ok(())
+    }
+
+    /// The lines drawn over the scene, added anew for every frame
+    pub fn debug_lines(&mut self) -> &mut DebugLines {
+        &mut self.debug_lines
+    }
+
+    /// The text drawn over the scene, added anew for every frame
+    pub fn text(&mut self) -> &mut TextRenderer {
+        &mut self.text
+    }
+
+    /// Draws the vertex normals as short lines from now on, which needs the
+    /// `geometry_shader` feature. Only into targets created afterwards.
+    pub fn show_normals(&mut self) -> Result<(), AppError> {
+        if !self.device.enabled_features().geometry_shader {
+            println!("Warning: geometry shaders are not supported, normals can't be shown");
+            return Ok(());
+        }
+
+        let vs = normals_vs::Shader::load(self.device.clone())?;
+        let gs = normals_gs::Shader::load(self.device.clone())?;
+        let fs = normals_fs::Shader::load(self.device.clone())?;
+        let subpass =
+            Subpass::from(self.render_pass.clone(), 0).ok_or(AppError::MissingSubpass(0))?;
+
+        let pipelines =
+            self.variants.iter().map(|variant| {
+                let vs_constants =
+                    normals_vs::SpecializationConstants {
+                        offset_x: variant.offset_x,
+                        mirror_x: variant.mirror_x,
+                    };
+
+                Ok(Arc::new(
+                    GraphicsPipeline::start()
+                        .vertex_input(OneVertexOneInstanceDefinition::<Vertex, InstanceData>::new())
+                        .vertex_shader(vs.main_entry_point(), vs_constants)
+                        // Still fed triangles, the geometry shader outputs the lines
+                        .geometry_shader(gs.main_entry_point(), ())
+                        .viewports_scissors_dynamic(1)
+                        .fragment_shader(fs.main_entry_point(), ())
+                        .depth_stencil(depth_test(self.pipeline_options.depth_compare, false))
+                        .render_pass(subpass.clone())
+                        .build(self.device.clone())?
+                ) as Arc<dyn GraphicsPipelineAbstract + Send + Sync>)
+            }).collect::<Result<Vec<_>, AppError>>()?;
+
+        self.normals_pipelines = Some(pipelines);
+
+        Ok(())
+    }
+
+    /// Rebuilds the geometry's pipelines from the current sources of its shaders,
+    /// compiled at runtime. Their inputs, outputs and bindings must stay the
+    /// same as the ones built in, those are taken from the built in shaders.
+    /// The frames in flight keep using the previous pipelines.
+    #[cfg(debug_assertions)]
+    pub fn reload_shaders(&mut self) -> Result<(), AppError> {
+        let shaders_dir = Path::new(hot_reload::SHADERS_DIR);
+        let vs_words = hot_reload::compile(&shaders_dir.join("triangle.vert"), ShaderKind::Vertex)?;
+        let fs_words =
+            hot_reload::compile(&shaders_dir.join("triangle.frag"), ShaderKind::Fragment)?;
+
+        // Safe as long as the interfaces given match the SPIR-V, which is up to
+        // whoever edits the shaders
+        let pipelines = unsafe {
+            let vs_module = ShaderModule::from_words(self.device.clone(), &vs_words)?;
+            let fs_module = ShaderModule::from_words(self.device.clone(), &fs_words)?;
+            let main = CStr::from_bytes_with_nul_unchecked(b"main\0");
+
+            let vs_entry_point =
+                vs_module.graphics_entry_point(
+                    main, vs::MainInput, vs::MainOutput,
+                    vs::Layout(ShaderStages { vertex: true, .. ShaderStages::none() }),
+                    GraphicsShaderType::Vertex
+                );
+            let fs_entry_point =
+                fs_module.graphics_entry_point(
+                    main, fs::MainInput, fs::MainOutput,
+                    fs::Layout(ShaderStages { fragment: true, .. ShaderStages::none() }),
+                    GraphicsShaderType::Fragment
+                );
+
+            create_pipelines(
+                &self.device, vs_entry_point, fs_entry_point, &self.render_pass, self.variants,
+                self.pipeline_options
+            )?
+        };
+
+        self.pipelines = pipelines;
+        println!("Reloaded the shaders");
+
+        Ok(())
+    }
+
+    /// Counts the pixels of every frame by luminance from now on, into targets
+    /// created afterwards
+    pub fn enable_histogram(&mut self) -> Result<(), AppError> {
+        if !self.queue.family().supports_compute() {
+            println!("Warning: the graphics queue doesn't support compute, the histogram is disabled");
+            return Ok(());
+        }
+
+        self.histogram = Some(Histogram::new(self.device.clone())?);
+
+        Ok(())
+    }
+
+    /// The luminance histogram of the last frame drawn into the image
+    /// `image_num` of `targets`, which the GPU must be done with. `None`
+    /// unless the histogram was enabled when they were created.
+    pub fn read_histogram(&self, targets: &Targets, image_num: usize) -> Result<Option<Vec<u32>>, AppError> {
+        match &targets.histograms {
+            Some(histograms) => Ok(Some(histograms[image_num].read()?)),
+            None => Ok(None),
+        }
+    }
+
+    /// Displaces the vertices along a wave from now on, the same one as
+    /// `make_wave` but computed on the GPU at the start of every frame from
+    /// the vertices as they are now
+    pub fn animate_on_gpu(&mut self) -> Result<(), AppError> {
+        if !self.queue.family().supports_compute() {
+            println!("Warning: the graphics queue doesn't support compute, the wave is disabled");
+            return Ok(());
+        }
+
+        let shader = wave_cs::Shader::load(self.device.clone())?;
+        let pipeline =
+            Arc::new(ComputePipeline::new(self.device.clone(), &shader.main_entry_point(), &())?);
+
+        let base_vertices =
+            upload_to_device_local(
+                &self.device, &self.queue, &self.vertices,
+                BufferUsage { storage_buffer: true, .. BufferUsage::none() }
+            )?;
+
+        self.gpu_wave = Some(GpuWave { pipeline, base_vertices });
+
+        Ok(())
+    }
+
+    /// Copies the vertices frames are currently drawn from, as the CPU last
+    /// set them: without the GPU wave
+    pub fn read_vertices(&self) -> Result<Vec<Vertex>, AppError> {
+        Ok(self.vertices.clone())
+    }
+
+    /// Which of the two vertex buffers frames are currently drawn from
+    pub fn current_vertex_buffer(&self) -> usize {
+        self.current_vertex_buffer
+    }
+
+    /// Writes `vertices` into the vertex buffer frames aren't drawn from, then
+    /// draws the next frames from it. The GPU must be done with the frames
+    /// which last used that buffer. Indices, if any, must stay valid, and the
+    /// order of translucent triangles is still sorted from their first shape.
+    pub fn update_vertices(&mut self, vertices: &[Vertex]) -> Result<(), AppError> {
+        let next = 1 - self.current_vertex_buffer;
+
+        // Written in place when the size allows it, saving an allocation.
+        // Vertices changing every frame are better off in host visible memory
+        // than copied over from it each time.
+        match &self.vertex_buffers[next] {
+            VertexBuffer::HostVisible(buffer) if buffer.len() == vertices.len() =>
+                buffer.write()?.copy_from_slice(vertices),
+            _ =>
+                self.vertex_buffers[next] =
+                    VertexBuffer::HostVisible(CpuAccessibleBuffer::from_iter(
+                        self.device.clone(), BufferUsage::all(),
+                        vertices.iter().cloned()
+                    )?),
+        }
+
+        self.vertices.clear();
+        self.vertices.extend_from_slice(vertices);
+        self.current_vertex_buffer = next;
+
+        Ok(())
+    }
+
+    /// Moves the point the scene is looked at from and turns the camera to look
+    /// along `direction`, starting with the next frame
+    pub fn set_camera(&mut self, position: [f32; 3], direction: [f32; 3]) {
+        self.camera_position = position;
+        self.camera_direction = direction;
+    }
+
+    /// Uploads the indices of `triangles` ordered from the farthest to the
+    /// nearest, as seen from the camera `time` seconds into the animation
+    fn sort_back_to_front(&self, triangles: &[Triangle], time: f32)
+        -> Result<IndexBuffer, AppError>
+    {
+        // Same spin as the vertex shader's
+        let model = Matrix4::from_angle_z(Rad(time));
+        let camera = Point3::from(self.camera_position);
+        let distance = |triangle: &Triangle|
+            model.transform_point(Point3::from(triangle.center)).distance2(camera);
+
+        let mut sorted = triangles.iter().collect::<Vec<_>>();
+        sorted.sort_by(|a, b| distance(b).partial_cmp(&distance(a)).unwrap_or(Ordering::Equal));
+
+        let indices =
+            sorted.into_iter().flat_map(|triangle| triangle.indices.iter().cloned()).collect::<Vec<_>>();
+
+        Ok(IndexBuffer::U32(CpuAccessibleBuffer::from_iter(
+            self.device.clone(), BufferUsage::index_buffer(),
+            indices.into_iter()
+        )?))
+    }
+
+    /// Returns the uploads the next submitted frame has to wait for, once
+    pub fn take_pending_uploads(&mut self) -> Option<UploadFuture> {
+        self.pending_uploads.take()
+    }
+
+    /// Records the commands drawing the scene as it is `time` seconds into the
+    /// animation, into the image `image_num` of `targets`. The last frame drawn
+    /// into that image must be done. The uniforms go into the next slot of
+    /// `targets`, whose frame `Targets::track_frame` should be given.
+    pub fn draw(&self, targets: &mut Targets, image_num: usize, time: f32)
+        -> Result<AutoCommandBuffer, AppError>
+    {
+        let mut uniforms = {
+            // The projection follows the letterboxed viewport rather than the
+            // swapchain, recreated along with the targets on every resize
+            build_uniforms(
+                self.projection, targets.viewport_dimensions, self.camera_position, self.camera_direction,
+                reverses_depth(self.pipeline_options.depth_compare)
+            )
+        };
+
+        // The vertex shader applies the spin after the model matrix, so does
+        // this. Its normal matrix is the rotation itself.
+        if self.spin_on_cpu {
+            let rotation = Matrix4::from_angle_z(Rad(time));
+            uniforms.model = (rotation * Matrix4::from(uniforms.model)).into();
+            uniforms.normal_matrix = (rotation * Matrix4::from(uniforms.normal_matrix)).into();
+        }
+        // The debug lines are already in world space
+        let view_projection = uniforms.view_projection;
+
+        // Only the camera and window size change what's in the buffer now,
+        // while the animation uses the push constants, recorded straight into
+        // the command buffer at 84 bytes per draw. The slot written is bound
+        // by the sets of its own.
+        targets.uniform_offset = targets.uniforms.write_uniform(uniforms)?;
+        let slot = targets.uniforms.slot_index(targets.uniform_offset);
+
+        // The vertex shader spins the geometry by the time, which is then
+        // already done
+        let push_constants = fs::ty::PushConstants {
+            object: Transform::IDENTITY.to_matrix(),
+            color: hue_to_rgba(time * 0.25),
+            time: if self.spin_on_cpu { 0.0 } else { time },
+        };
+
+        let pipelines =
+            match (self.topology, &self.pipelines.wireframe) {
+                (Topology::Triangles, Some(wireframe_pipelines)) if self.wireframe => wireframe_pipelines,
+                (Topology::Triangles, _) if self.culling => &self.pipelines.culled,
+                (Topology::Triangles, _) => &self.pipelines.triangles,
+                (Topology::LineStrip, _) => &self.pipelines.line_strip,
+                (Topology::Points, _) => &self.pipelines.points,
+            };
+
+        let dynamic_state =
+            if self.masked { &targets.masked_dynamic_state } else { &targets.scene_dynamic_state };
+        // Only the pipelines drawing lines take their width from the dynamic
+        // state, and must be given one
+        let line_dynamic_state = DynamicState { line_width: Some(self.line_width), .. dynamic_state.clone() };
+        let draws_lines =
+            match self.topology {
+                Topology::Triangles => self.wireframe && self.pipelines.wireframe.is_some(),
+                Topology::LineStrip => true,
+                Topology::Points => false,
+            };
+
+        let geometry_dynamic_state = if draws_lines { &line_dynamic_state } else { dynamic_state };
+        let mut passes = vec![(pipelines, targets.sets[slot].clone(), geometry_dynamic_state)];
+        if let (Some(normals_pipelines), Some(normals_sets)) = (&self.normals_pipelines, &targets.normals_sets) {
+            passes.push((normals_pipelines, normals_sets[slot].clone(), dynamic_state));
+        }
+
+        let mut builder =
+            AutoCommandBufferBuilder::primary_one_time_submit(
+                self.device.clone(), self.queue.family()
+            )?;
+
+        let vertex_buffer = self.vertex_buffers[self.current_vertex_buffer].access();
+
+        // Dispatches can't happen within a render pass, so the wave is computed
+        // before it starts. The vertices it writes are then read as vertex
+        // attributes by the draws, which in Vulkan needs a pipeline barrier from
+        // the compute shader writes to the vertex input reads in between.
+        // vulkano tracks how each command accesses the buffer and inserts that
+        // barrier itself when the command buffer is built.
+        if let Some(GpuWave { pipeline, base_vertices }) = &self.gpu_wave {
+            let set =
+                Arc::new(
+                    PersistentDescriptorSet::start(pipeline.clone(), 0)
+                        .add_buffer(base_vertices.clone())?
+                        .add_buffer(vertex_buffer.clone())?
+                        .build()?
+                );
+
+            let group_count = (base_vertices.len() as u32).div_ceil(WAVE_GROUP_SIZE);
+            builder =
+                builder.dispatch(
+                    [group_count, 1, 1], pipeline.clone(), set,
+                    wave_cs::ty::PushConstants { time }
+                )?;
+        }
+        if let Some(particles) = &self.particles {
+            builder = particles.update(builder)?;
+        }
+
+        let sorted_index_buffer = match &self.translucent_triangles {
+            Some(triangles) => Some(self.sort_back_to_front(triangles, time)?),
+            None => None,
+        };
+        let index_buffer = sorted_index_buffer.as_ref().or(self.index_buffer.as_ref());
+
+        // Each object is a draw of its own, only its push constants change
+        let draws =
+            passes.iter()
+                .flat_map(|&(pipelines, ref set, dynamic_state)|
+                    pipelines.iter().map(move |pipeline| (pipeline, set, dynamic_state)))
+                .flat_map(|(pipeline, set, dynamic_state)|
+                    self.objects.iter().map(move |object| {
+                        let object = object.to_matrix();
+                        let push_constants = fs::ty::PushConstants { object, .. push_constants };
+                        GeometryDraw { pipeline, set, dynamic_state, push_constants }
+                    }))
+                .collect::<Vec<_>>();
+        let instance_buffer = self.instance_buffer.clone() as Arc<dyn BufferAccess + Send + Sync>;
+
+        // Records what the first subpass draws behind the geometry, into
+        // either command buffer
+        let record_background = |mut builder: AutoCommandBufferBuilder| -> Result<_, AppError> {
+            // Covers the clear color, before anything else covers it
+            if let (Some(skybox), Projection::Perspective) = (&self.skybox, self.projection) {
+                let inverse_view_projection =
+                    build_sky_matrix(targets.viewport_dimensions, self.camera_direction);
+                builder = skybox.draw(builder, dynamic_state, inverse_view_projection)?;
+            }
+
+            // Marks where the geometry can be drawn, before it is
+            if let Some(stencil_mask_pipeline) = &self.stencil_mask_pipeline {
+                let [width, height] = targets.viewport_dimensions;
+                builder =
+                    builder.draw(
+                        stencil_mask_pipeline.clone(), dynamic_state,
+                        BufferlessVertices { vertices: 3 * STENCIL_MASK_SEGMENTS as usize, instances: 1 },
+                        (), stencil_mask_vs::ty::PushConstants { aspect_ratio: width as f32 / height as f32 }
+                    )?;
+            }
+
+            Ok(builder)
+        };
+
+        let clear_values =
+            make_clear_values(
+                &self.render_pass, self.clear_color, far_depth(self.pipeline_options.depth_compare)
+            );
+        let builder =
+            if self.records_on_secondary() {
+                let subpass = Subpass::from(self.render_pass.clone(), 0).ok_or(AppError::MissingSubpass(0))?;
+                let secondary_builder =
+                    AutoCommandBufferBuilder::secondary_graphics_one_time_submit(
+                        self.device.clone(), self.queue.family(), subpass.clone()
+                    )?;
+                let secondary_builder = record_background(secondary_builder)?;
+
+                // The instances are split among the workers, behind the
+                // background recorded here, or all drawn along with it
+                let (background, chunks) =
+                    match &self.recording_pool {
+                        Some(pool) => {
+                            let chunks =
+                                self.record_instance_chunks(
+                                    pool, &subpass, &draws, &vertex_buffer, index_buffer
+                                )?;
+                            (secondary_builder.build()?, chunks)
+                        },
+                        None =>
+                            (
+                                record_instances(
+                                    secondary_builder, &draws, &vertex_buffer, &instance_buffer, index_buffer
+                                )?.build()?,
+                                Vec::new()
+                            ),
+                    };
+
+                // Safe as the geometry only reads what nothing else writes
+                // while frames are in flight, checked by
+                // `records_on_secondary`, the uniforms being guarded by their
+                // ring's fences
+                let mut builder =
+                    builder.begin_render_pass(targets.scene_framebuffer.clone(), true, clear_values)?;
+                for secondary in iter::once(background).chain(chunks) {
+                    builder = unsafe { builder.execute_commands(secondary)? };
+                }
+                builder
+            } else {
+                let builder =
+                    record_background(
+                        builder.begin_render_pass(targets.scene_framebuffer.clone(), false, clear_values)?
+                    )?;
+                let builder =
+                    record_instances(builder, &draws, &vertex_buffer, &instance_buffer, index_buffer)?;
+                match &self.particles {
+                    Some(particles) => particles.draw(builder, dynamic_state)?,
+                    None => builder,
+                }
+            };
+
+        let builder =
+            self.debug_lines.draw(builder.next_subpass(false)?, &line_dynamic_state, view_projection)?;
+
+        // Sampling the scene image waits for the first render pass to be done
+        // writing it, vulkano inserts the barrier and the layout transition
+        // between both
+        let post_push_constants = post_fs::ty::PushConstants { blur_radius: self.blur_radius as i32 };
+
+        let builder = builder
+            .end_render_pass()?
+            .begin_render_pass(
+                targets.framebuffers[image_num].clone(), false,
+                make_clear_values(&self.post_render_pass, LETTERBOX_COLOR, 1.0)
+            )?
+            .draw(
+                self.post_pipeline.clone(), &targets.dynamic_state,
+                BufferlessVertices { vertices: 3, instances: 1 }, targets.post_set.clone(),
+                post_push_constants
+            )?;
+        let builder = self.text.draw(builder, &targets.dynamic_state, targets.viewport_dimensions)?
+            .end_render_pass()?;
+
+        let builder = match (&self.histogram, &targets.histograms) {
+            (Some(histogram), Some(histograms)) => histogram.record(builder, &histograms[image_num])?,
+            _ => builder,
+        };
+
+        Ok(builder.build()?)
+    }
+}
+
+/// Records `draws` of every vertex of `vertex_buffer`, or those of
+/// `index_buffer` when there's one, for each instance of `instance_buffer`
+fn record_instances(
+    mut builder: AutoCommandBufferBuilder,
+    draws: &[GeometryDraw],
+    vertex_buffer: &Arc<dyn BufferAccess + Send + Sync>,
+    instance_buffer: &Arc<dyn BufferAccess + Send + Sync>,
+    index_buffer: Option<&IndexBuffer>
+) -> Result<AutoCommandBufferBuilder, AppError> {
+    // The vertex, index and instance counts are taken from the buffers' lengths
+    for draw in draws {
+        let vertex_buffers = vec![vertex_buffer.clone(), instance_buffer.clone()];
+
+        builder = match index_buffer {
+            Some(IndexBuffer::U16(index_buffer)) =>
+                builder.draw_indexed(
+                    draw.pipeline.clone(), draw.dynamic_state,
+                    vertex_buffers, index_buffer.clone(), draw.set.clone(), draw.push_constants
+                )?,
+            Some(IndexBuffer::U32(index_buffer)) =>
+                builder.draw_indexed(
+                    draw.pipeline.clone(), draw.dynamic_state,
+                    vertex_buffers, index_buffer.clone(), draw.set.clone(), draw.push_constants
+                )?,
+            None =>
+                builder.draw(
+                    draw.pipeline.clone(), draw.dynamic_state,
+                    vertex_buffers, draw.set.clone(), draw.push_constants
+                )?,
+        };
+    }
+
+    Ok(builder)
+}
+
+/// Splits `0 .. count` into up to `chunk_count` consecutive ranges, none
+/// empty, whose lengths differ by one at most
+fn split_evenly(count: usize, chunk_count: usize) -> Vec<Range<usize>> {
+    let chunk_count = chunk_count.clamp(1, count.max(1));
+    (0 .. chunk_count)
+        .map(|chunk| chunk * count / chunk_count .. (chunk + 1) * count / chunk_count)
+        .collect()
+}
+
+/// Builds a pipeline per variant drawing the geometry with the `vs` and `fs`
+/// shaders for each topology, plus the triangles culling their back faces,
+/// and only drawing their edges if the device can
+fn create_pipelines(
+    device: &Arc<Device>,
+    vs: VsEntryPoint,
+    fs: FsEntryPoint,
+    render_pass: &Arc<dyn RenderPassAbstract + Send + Sync>,
+    variants: &[Variant],
+    options: PipelineOptions
+) -> Result<GeometryPipelines, AppError> {
+    let create_pipelines = |topology, wireframe, culled| {
+        variants.iter()
+            .map(|variant|
+                create_pipeline(
+                    device.clone(), vs.clone(), fs.clone(), render_pass.clone(), variant,
+                    topology, wireframe, culled, options
+                ))
+            .collect::<Result<Vec<_>, AppError>>()
+    };
+
+    let wireframe =
+        if device.enabled_features().fill_mode_non_solid {
+            Some(create_pipelines(Topology::Triangles, true, false)?)
+        } else {
+            None
+        };
+
+    Ok(GeometryPipelines {
+        triangles: create_pipelines(Topology::Triangles, false, false)?,
+        wireframe,
+        culled: create_pipelines(Topology::Triangles, false, true)?,
+        line_strip: create_pipelines(Topology::LineStrip, false, false)?,
+        points: create_pipelines(Topology::Points, false, false)?,
+    })
+}
+
+/// Builds a pipeline drawing the geometry specialized for `variant` as
+/// `topology`, rasterizing only the polygon edges when `wireframe` is set,
+/// which needs the `fill_mode_non_solid` feature, discarding the faces wound
+/// clockwise when `culled` is set, and treating what's already drawn as
+/// `options` tell. Points are drawn larger than a pixel when the `large_points`
+/// feature allows it. Lines, the wireframe's included, take their width from
+/// the dynamic state.
+#[allow(clippy::too_many_arguments)]
+fn create_pipeline(
+    device: Arc<Device>,
+    vs: VsEntryPoint,
+    fs: FsEntryPoint,
+    render_pass: Arc<dyn RenderPassAbstract + Send + Sync>,
+    variant: &Variant,
+    topology: Topology,
+    wireframe: bool,
+    culled: bool,
+    options: PipelineOptions
+) -> Result<Arc<dyn GraphicsPipelineAbstract + Send + Sync>, AppError> {
+    let limits = device.physical_device().limits();
+    let point_size =
+        if device.enabled_features().large_points { POINT_SIZE.min(limits.point_size_range()[1]) } else { 1.0 };
+
+    let vs_constants =
+        vs::SpecializationConstants { offset_x: variant.offset_x, point_size, mirror_x: variant.mirror_x };
+
+    let [base_r, base_g, base_b] = variant.base_color;
+    let fs_constants = fs::SpecializationConstants { base_r, base_g, base_b };
+
+    let builder =
+        GraphicsPipeline::start()
+            // Defines what kind of vertex input is expected: the geometry, then
+            // one `InstanceData` per copy of it.
+            .vertex_input(OneVertexOneInstanceDefinition::<Vertex, InstanceData>::new())
+            // The vertex shader.
+            .vertex_shader(vs, vs_constants)
+            // Defines the viewport.
+            .viewports_scissors_dynamic(1)
+            // The fragment shader.
+            .fragment_shader(fs, fs_constants)
+            // Discards fragments hidden behind already drawn ones, and those
+            // outside of the stencil mask when it's tested.
+            .depth_stencil(depth_test(options.depth_compare, options.stencil_tested))
+            // This graphics pipeline object concerns the first pass of the render pass,
+            // its sample count is taken from there.
+            .render_pass(
+                Subpass::from(render_pass.clone(), 0).ok_or(AppError::MissingSubpass(0))?
+            );
+
+    let builder = match topology {
+        Topology::Triangles => builder.triangle_list(),
+        Topology::LineStrip => builder.line_strip().line_width_dynamic(),
+        Topology::Points => builder.point_list(),
+    };
+    let builder = if wireframe { builder.polygon_mode_line().line_width_dynamic() } else { builder };
+    // The projection flips Y for Vulkan, which keeps the winding the same as
+    // seen on screen
+    let builder = if culled { builder.cull_mode_back().front_face_counter_clockwise() } else { builder };
+
+    let builder =
+        if options.translucent {
+            builder
+                // Same as `.blend_alpha_blending()`, spelled out to show what can
+                // be tuned: color = src * src_alpha + dst * (1 - src_alpha)
+                .blend_collective(AttachmentBlend {
+                    enabled: true,
+                    color_op: BlendOp::Add,
+                    color_source: BlendFactor::SrcAlpha,
+                    color_destination: BlendFactor::OneMinusSrcAlpha,
+                    alpha_op: BlendOp::Add,
+                    alpha_source: BlendFactor::SrcAlpha,
+                    alpha_destination: BlendFactor::OneMinusSrcAlpha,
+                    mask_red: true,
+                    mask_green: true,
+                    mask_blue: true,
+                    mask_alpha: true,
+                })
+                // Still hidden behind opaque geometry, but never hiding the
+                // translucent triangles drawn after them
+                .depth_write(false)
+        } else {
+            builder
+        };
+
+    Ok(Arc::new(builder.build(device)?))
+}
+
+/// Returns `requested` if color, depth and stencil attachments all support
+/// that many samples on `device`, and so do images of `format`, or else the
+/// highest lower count they do support. As in `choose_depth_format`, the
+/// format is checked by creating a tiny image with it.
+fn choose_sample_count(device: &Arc<Device>, format: Format, requested: u32) -> u32 {
+    let limits = device.physical_device().limits();
+    // Bit N is set when 2^N samples are supported
+    let supported =
+        limits.framebuffer_color_sample_counts()
+            & limits.framebuffer_depth_sample_counts()
+            & limits.framebuffer_stencil_sample_counts();
+    let format_supports = |samples: u32|
+        AttachmentImage::transient_multisampled(device.clone(), [1, 1], samples, format).is_ok();
+
+    let mut samples = requested.next_power_of_two().min(64);
+    while samples > 1 && (supported & samples == 0 || !format_supports(samples)) {
+        samples /= 2;
+    }
+
+    if samples != requested {
+        println!(
+            "Warning: {} MSAA samples are not supported, using {} instead",
+            requested, samples);
+    }
+
+    samples
+}
+
+/// `width` within the line widths `device` supports, only 1 without the
+/// `wide_lines` feature
+fn clamp_line_width(device: &Device, width: f32) -> f32 {
+    if !device.enabled_features().wide_lines {
+        return 1.0;
+    }
+
+    let [min, max] = device.physical_device().limits().line_width_range();
+    width.clamp(min, max)
+}
+
+/// The clear values `begin_render_pass` takes for `render_pass`, one per
+/// attachment in the order it declares them. Those cleared on load get
+/// `clear_color` for colors, or `far_depth` and a zero stencil, and the others
+/// none, so that adding or removing an attachment keeps them in line.
+fn make_clear_values(
+    render_pass: &Arc<dyn RenderPassAbstract + Send + Sync>,
+    clear_color: [f32; 4],
+    far_depth: f32
+) -> Vec<ClearValue> {
+    (0 .. render_pass.num_attachments())
+        .filter_map(|index| render_pass.attachment_desc(index))
+        .map(|attachment| {
+            if !matches!(attachment.load, LoadOp::Clear) {
+                return ClearValue::None;
+            }
+
+            match attachment.format.ty() {
+                FormatTy::Depth => ClearValue::Depth(far_depth),
+                FormatTy::Stencil => ClearValue::Stencil(0),
+                FormatTy::DepthStencil => ClearValue::DepthStencil((far_depth, 0)),
+                _ => clear_color.into(),
+            }
+        })
+        .collect()
+}
+
+/// Returns the first of `DEPTH_STENCIL_FORMATS` the device can use as a depth
+/// attachment with `samples` samples, so that the stencil mask can be enabled
+/// later on, or else `D16Unorm` which every device supports. vulkano 0.13 has
+/// no way of querying a format's properties, so a tiny image is created with
+/// each of them until one works.
+fn choose_depth_format(device: &Arc<Device>, samples: u32) -> Format {
+    let supported =
+        DEPTH_STENCIL_FORMATS.iter()
+            .find(|&&format|
+                AttachmentImage::transient_multisampled(device.clone(), [1, 1], samples, format).is_ok());
+
+    match supported {
+        Some(&format) => format,
+        None => {
+            println!("Warning: no depth and stencil format is supported, the stencil mask is disabled");
+            Format::D16Unorm
+        },
+    }
+}
+
+/// Whether `compare` keeps the nearest fragments only once the depth range is
+/// reversed, the far plane at 0 and the near one at 1. That's reverse-Z: the
+/// picture is the same as with `Less`, but floats being most precise near 0,
+/// their precision then makes up for the perspective crowding the far depths
+/// together, which keeps distant surfaces from flickering through each other.
+fn reverses_depth(compare: Compare) -> bool {
+    matches!(compare, Compare::Greater | Compare::GreaterOrEqual)
+}
+
+/// The depth the depth buffer is cleared to with `compare`, that of the far
+/// plane, so that whatever is drawn first passes the test
+fn far_depth(compare: Compare) -> f32 {
+    if reverses_depth(compare) { 0.0 } else { 1.0 }
+}
+
+/// The depth test of `DepthStencil::simple_depth_test` comparing with
+/// `compare`, also passing only where `stencil_tested` asks for the stencil
+/// mask to have been drawn
+fn depth_test(compare: Compare, stencil_tested: bool) -> DepthStencil {
+    let depth_stencil =
+        if stencil_tested { stencil_tested_depth() } else { DepthStencil::simple_depth_test() };
+
+    DepthStencil { depth_compare: compare, .. depth_stencil }
+}
+
+/// The depth test of `DepthStencil::simple_depth_test`, only passing where the
+/// stencil mask was drawn too
+fn stencil_tested_depth() -> DepthStencil {
+    let stencil = Stencil {
+        compare: Compare::Equal,
+        pass_op: StencilOp::Keep,
+        fail_op: StencilOp::Keep,
+        depth_fail_op: StencilOp::Keep,
+        compare_mask: Some(0xff),
+        write_mask: Some(0),
+        reference: Some(STENCIL_MASK_REFERENCE),
+    };
+
+    DepthStencil {
+        stencil_front: stencil,
+        stencil_back: stencil,
+        .. DepthStencil::simple_depth_test()
+    }
+}
+
+/// Generates a triangle list covering the [-1, 1] square, which fills the
+/// window from the starting camera position, split into `rows` by `cols` cells
+/// of two triangles each
+fn make_grid(rows: u32, cols: u32) -> Vec<Vertex> {
+    let vertex = |row: u32, col: u32| {
+        let u = col as f32 / cols as f32;
+        let v = row as f32 / rows as f32;
+
+        // Y points up, while images are stored top row first
+        Vertex {
+            position: [u * 2.0 - 1.0, v * 2.0 - 1.0, 0.0],
+            tex_coord: [u, 1.0 - v],
+            color: [1.0, 1.0, 1.0, 1.0],
+            normal: [0.0, 0.0, 1.0],
+            tangent: [0.0; 3],
+        }
+    };
+
+    let mut vertices = Vec::with_capacity((rows * cols * 6) as usize);
+    for row in 0..rows {
+        for col in 0..cols {
+            vertices.push(vertex(row, col));
+            vertices.push(vertex(row, col + 1));
+            vertices.push(vertex(row + 1, col + 1));
+
+            vertices.push(vertex(row + 1, col + 1));
+            vertices.push(vertex(row + 1, col));
+            vertices.push(vertex(row, col));
+        }
+    }
+
+    vertices
+}
+
+/// Copies `data` into a new buffer of `usage` in device local memory, which
+/// the GPU reads faster than host visible memory, through a host visible
+/// staging buffer. Waits for `queue` to be done with the copy, the buffer can
+/// then be used from its family.
+pub fn upload_to_device_local<T>(device: &Arc<Device>, queue: &Arc<Queue>, data: &[T], usage: BufferUsage)
+    -> Result<Arc<DeviceLocalBuffer<[T]>>, AppError>
+    where T: Copy + Send + Sync + 'static
+{
+    let staging =
+        CpuAccessibleBuffer::from_iter(device.clone(), BufferUsage::transfer_source(), data.iter().cloned())?;
+    let buffer =
+        DeviceLocalBuffer::array(
+            device.clone(), data.len(), BufferUsage { transfer_destination: true, .. usage },
+            Some(queue.family())
+        )?;
+
+    AutoCommandBufferBuilder::primary_one_time_submit(device.clone(), queue.family())?
+        .copy_buffer(staging, buffer.clone())?
+        .build()?
+        .execute(queue.clone())?
+        .then_signal_fence_and_flush()?
+        .wait(None)?;
+
+    Ok(buffer)
+}
+
+/// Lays `count` objects out evenly on a ring around the origin, each turned
+/// along the ring and shrunk so that they barely overlap. A single one stays
+/// in the middle.
+pub fn make_ring(count: u32) -> Vec<Transform> {
+    if count <= 1 {
+        return vec![Transform::IDENTITY];
+    }
+
+    let scale = (1.0 / count as f32).sqrt();
+    (0 .. count)
+        .map(|index| {
+            let angle = index as f32 / count as f32 * std::f32::consts::TAU;
+            Transform {
+                translation: [RING_RADIUS * angle.cos(), RING_RADIUS * angle.sin(), 0.0],
+                rotation: angle,
+                scale,
+            }
+        })
+        .collect()
+}
+
+/// Displaces `vertices` along Z by a wave travelling across them as `time`
+/// (in seconds) goes by
+pub fn make_wave(vertices: &[Vertex], time: f32) -> Vec<Vertex> {
+    vertices.iter().map(|vertex| {
+        let [x, y, z] = vertex.position;
+        let displacement = 0.1 * (4.0 * (x + y) + 2.0 * time).sin();
+
+        Vertex { position: [x, y, z + displacement], .. *vertex }
+    }).collect()
+}
+
+/// Sets the tangent of every vertex from the positions and texture coordinates
+/// of the `triangles` (indices into `vertices`) it belongs to, averaged over
+/// them and kept perpendicular to the vertex normal. The bitangent is left to
+/// the fragment shader, as the cross product of the normal and tangent.
+fn compute_tangents(vertices: &mut [Vertex], triangles: &[[usize; 3]]) {
+    let mut tangents = vec![Vector3::new(0.0, 0.0, 0.0); vertices.len()];
+
+    for triangle in triangles {
+        let [a, b, c] = [vertices[triangle[0]], vertices[triangle[1]], vertices[triangle[2]]];
+        let edge1 = Vector3::from(b.position) - Vector3::from(a.position);
+        let edge2 = Vector3::from(c.position) - Vector3::from(a.position);
+        let (du1, dv1) = (b.tex_coord[0] - a.tex_coord[0], b.tex_coord[1] - a.tex_coord[1]);
+        let (du2, dv2) = (c.tex_coord[0] - a.tex_coord[0], c.tex_coord[1] - a.tex_coord[1]);
+
+        // The texture coordinates don't span any area, they give no direction
+        let determinant = du1 * dv2 - du2 * dv1;
+        if determinant.abs() < f32::EPSILON {
+            continue;
+        }
+
+        // Solves edge = du * tangent + dv * bitangent for the tangent
+        let tangent = (edge1 * dv2 - edge2 * dv1) / determinant;
+        for &index in triangle {
+            tangents[index] += tangent;
+        }
+    }
+
+    for (vertex, tangent) in vertices.iter_mut().zip(tangents) {
+        let normal = Vector3::from(vertex.normal);
+        let tangent = tangent - normal * normal.dot(tangent);
+
+        // Any direction along the surface does without texture coordinates
+        let tangent =
+            if tangent.magnitude2() > f32::EPSILON {
+                tangent
+            } else if normal.x.abs() < 0.9 {
+                normal.cross(Vector3::unit_x()).cross(normal)
+            } else {
+                normal.cross(Vector3::unit_y()).cross(normal)
+            };
+
+        vertex.tangent = tangent.normalize().into();
+    }
+}
+
+/// Loads every model of the Wavefront OBJ file at `path` into a single indexed
+/// mesh, centered and scaled to fit in a unit cube like the quad does
+fn load_obj(path: &str) -> Result<(Vec<Vertex>, Indices), AppError> {
+    let options = tobj::LoadOptions { single_index: true, triangulate: true, .. Default::default() };
+    let (models, _materials) = tobj::load_obj(path, &options)?;
+
+    let mut vertices = Vec::new();
+    let mut indices = Vec::new();
+    for model in models {
+        let mesh = model.mesh;
+        let first_index = vertices.len() as u32;
+
+        for i in 0 .. mesh.positions.len() / 3 {
+            let normal = match mesh.normals.get(3 * i .. 3 * i + 3) {
+                Some(normal) => [normal[0], normal[1], normal[2]],
+                None => [0.0, 0.0, 1.0],
+            };
+
+            // OBJ texture coordinates start from the bottom row
+            let tex_coord = match mesh.texcoords.get(2 * i .. 2 * i + 2) {
+                Some(tex_coord) => [tex_coord[0], 1.0 - tex_coord[1]],
+                None => [0.0, 0.0],
+            };
+
+            vertices.push(Vertex {
+                position: [mesh.positions[3 * i], mesh.positions[3 * i + 1], mesh.positions[3 * i + 2]],
+                tex_coord,
+                color: [1.0, 1.0, 1.0, 1.0],
+                normal,
+                tangent: [0.0; 3],
+            });
+        }
+
+        // Indices are still relative to this model's own vertices here
+        check_indices(&mesh.indices, mesh.positions.len() / 3)?;
+        indices.extend(mesh.indices.iter().map(|&index| first_index + index));
+    }
+
+    check_mesh(vertices.len(), &indices)?;
+    fit_in_unit_cube(&mut vertices);
+    let indices = narrow_indices(indices, vertices.len());
+
+    Ok((vertices, indices))
+}
+
+/// Loads the first primitive of the first mesh of the glTF file at `path`,
+/// centered and scaled like `load_obj`, along with its base color texture when
+/// it has one. glTF is Y up like the scene and its texture coordinates start
+/// from the top row like Vulkan's, both are kept as they are. Skins,
+/// animations and the node transforms are left out.
+fn load_gltf(path: &str) -> Result<(Vec<Vertex>, Indices, Option<RgbaImage>), AppError> {
+    let (document, buffers, images) = gltf::import(path)?;
+
+    if document.skins().len() > 0 {
+        println!("Warning: skins are not supported, {} is drawn in its bind pose", path);
+    }
+    if document.animations().len() > 0 {
+        println!("Warning: animations are not supported, {} is drawn still", path);
+    }
+
+    let mesh = document.meshes().next().ok_or(AppError::UnsupportedModel("it has no mesh"))?;
+    if document.meshes().len() > 1 || mesh.primitives().len() > 1 {
+        println!("Warning: only the first primitive of the first mesh of {} is drawn", path);
+    }
+
+    let primitive = mesh.primitives().next().ok_or(AppError::UnsupportedModel("its mesh has no primitive"))?;
+    if primitive.mode() != gltf::mesh::Mode::Triangles {
+        return Err(AppError::UnsupportedModel("its primitive isn't a triangle list"));
+    }
+
+    let pbr = primitive.material().pbr_metallic_roughness();
+    let base_color_texture = pbr.base_color_texture();
+    let tex_coord_set = base_color_texture.as_ref().map_or(0, |info| info.tex_coord());
+
+    let reader = primitive.reader(|buffer| buffers.get(buffer.index()).map(|data| data.0.as_slice()));
+    let positions =
+        reader.read_positions().ok_or(AppError::UnsupportedModel("its primitive has no positions"))?;
+    let normals: Vec<[f32; 3]> = reader.read_normals().map_or_else(Vec::new, |normals| normals.collect());
+    let tex_coords: Vec<[f32; 2]> =
+        reader.read_tex_coords(tex_coord_set)
+            .map_or_else(Vec::new, |tex_coords| tex_coords.into_f32().collect());
+
+    // The base color factor tints the whole primitive, as the vertex colors do
+    let color = pbr.base_color_factor();
+    let mut vertices: Vec<Vertex> =
+        positions.enumerate().map(|(i, position)| Vertex {
+            position,
+            tex_coord: tex_coords.get(i).copied().unwrap_or([0.0, 0.0]),
+            color,
+            normal: normals.get(i).copied().unwrap_or([0.0, 0.0, 1.0]),
+            tangent: [0.0; 3],
+        }).collect();
+
+    // Primitives without indices draw their vertices in order
+    let indices: Vec<u32> =
+        match reader.read_indices() {
+            Some(indices) => indices.into_u32().collect(),
+            None => (0 .. vertices.len() as u32).collect(),
+        };
+
+    check_mesh(vertices.len(), &indices)?;
+    fit_in_unit_cube(&mut vertices);
+    let indices = narrow_indices(indices, vertices.len());
+
+    let base_color =
+        base_color_texture.and_then(|info| gltf_image_to_rgba(&images[info.texture().source().index()]));
+
+    Ok((vertices, indices, base_color))
+}
+
+/// Converts an image decoded from a glTF file to RGBA. `None`, with a warning,
+/// for those with more than 8 bits per channel.
+fn gltf_image_to_rgba(image: &gltf::image::Data) -> Option<RgbaImage> {
+    use gltf::image::Format;
+
+    // One or two channels come from grayscale images, the second one being
+    // the alpha
+    let pixels: Vec<u8> =
+        match image.format {
+            Format::R8 => image.pixels.iter().flat_map(|&l| [l, l, l, 255]).collect(),
+            Format::R8G8 => image.pixels.chunks(2).flat_map(|p| [p[0], p[0], p[0], p[1]]).collect(),
+            Format::R8G8B8 => image.pixels.chunks(3).flat_map(|p| [p[0], p[1], p[2], 255]).collect(),
+            Format::R8G8B8A8 => image.pixels.clone(),
+            format => {
+                println!("Warning: {:?} textures are not supported, the model keeps the default one", format);
+                return None;
+            },
+        };
+
+    RgbaImage::from_raw(image.width, image.height, pixels)
+}
+
+/// Centers `vertices` and scales them to fit in a unit cube, as the quad does
+fn fit_in_unit_cube(vertices: &mut [Vertex]) {
+    let mut min = [f32::MAX; 3];
+    let mut max = [f32::MIN; 3];
+    for vertex in vertices.iter() {
+        for axis in 0 .. 3 {
+            min[axis] = min[axis].min(vertex.position[axis]);
+            max[axis] = max[axis].max(vertex.position[axis]);
+        }
+    }
+
+    let size = (0 .. 3).map(|axis| max[axis] - min[axis]).fold(0.0, f32::max);
+    if size > 0.0 {
+        for vertex in vertices.iter_mut() {
+            for axis in 0 .. 3 {
+                let center = (min[axis] + max[axis]) / 2.0;
+                vertex.position[axis] = (vertex.position[axis] - center) / size;
+            }
+        }
+    }
+}
+
+/// Fails unless every index of `indices` is one of `vertex_count` vertices,
+/// which malformed files don't ensure
+fn check_indices(indices: &[u32], vertex_count: usize) -> Result<(), AppError> {
+    if indices.iter().any(|&index| index as usize >= vertex_count) {
+        return Err(AppError::UnsupportedModel("an index is out of the range of its vertices"));
+    }
+
+    Ok(())
+}
+
+/// Fails unless the mesh of `vertex_count` vertices has triangles, all of
+/// whose `indices` are within its vertices. Empty meshes would otherwise end
+/// up in empty buffers, which Vulkan doesn't allow.
+fn check_mesh(vertex_count: usize, indices: &[u32]) -> Result<(), AppError> {
+    if vertex_count == 0 || indices.is_empty() {
+        return Err(AppError::UnsupportedModel("it has no triangles"));
+    }
+
+    check_indices(indices, vertex_count)
+}
+
+/// Stores `indices` into `vertex_count` vertices in 16 bits when they fit,
+/// which takes half the memory of 32 bits
+fn narrow_indices(indices: Vec<u32>, vertex_count: usize) -> Indices {
+    if vertex_count <= 1 << 16 {
+        Indices::U16(indices.into_iter().map(|index| index as u16).collect())
+    } else {
+        Indices::U32(indices)
+    }
+}
+
+/// Spreads `count` copies of the geometry over a square-ish grid covering the
+/// [-1, 1] square, shrunk to fit in their cell and tinted along a gradient
+/// from the bottom left corner to the top right one. A single copy is left as
+/// is.
+fn make_instances(count: u32) -> Vec<InstanceData> {
+    if count <= 1 {
+        return vec![InstanceData { offset: [0.0, 0.0], scale: 1.0, instance_color: [1.0; 4] }];
+    }
+
+    let cols = (count as f32).sqrt().ceil() as u32;
+    let rows = count.div_ceil(cols);
+    let cell_size = 2.0 / cols.max(rows) as f32;
+
+    (0 .. count)
+        .map(|i| {
+            let (row, col) = (i / cols, i % cols);
+
+            InstanceData {
+                offset: [
+                    -1.0 + (col as f32 + 0.5) * cell_size,
+                    -1.0 + (row as f32 + 0.5) * cell_size,
+                ],
+                // The quad is 1 wide, leaves some space between the copies
+                scale: cell_size * 0.8,
+                // Stops short of wrapping back to red in the far corner
+                instance_color: hue_to_rgba(0.8 * (row + col) as f32 / (rows + cols - 2).max(1) as f32),
+            }
+        })
+        .collect()
+}
+
+/// Builds the uniforms of the geometry: how it's seen from a camera looking
+/// along `camera_direction`, with the near plane at depth 1 and the far one at
+/// 0 when `reversed_depth`, where it's placed, the matrix transforming its
+/// normals and the light. The vertex shader spins it as the time pushed goes by.
+fn build_uniforms(
+    projection: Projection,
+    viewport_dimensions: [u32; 2],
+    camera_position: [f32; 3],
+    camera_direction: [f32; 3],
+    reversed_depth: bool
+) -> vs::ty::Data {
+    let [width, height] = [viewport_dimensions[0] as f32, viewport_dimensions[1] as f32];
+
+    let (model, view, projection) = match projection {
+        Projection::Perspective => {
+            let view =
+                Matrix4::look_at_dir(
+                    Point3::from(camera_position),
+                    Vector3::from(camera_direction),
+                    Vector3::new(0.0, 1.0, 0.0)
+                );
+
+            (Matrix4::identity(), view, perspective_projection(width, height))
+        },
+        Projection::Orthographic => {
+            // The world, from -1 to 1 with Y pointing up, is placed so that it
+            // fills the shortest side of the viewport, in the middle of it. The
+            // vertex shader spins the geometry before this applies, around the
+            // world origin rather than the top left corner.
+            let half_size = width.min(height) / 2.0;
+            let view =
+                Matrix4::from_translation(Vector3::new(width / 2.0, height / 2.0, 0.0))
+                    * Matrix4::from_nonuniform_scale(half_size, -half_size, half_size);
+
+            // Y points down, as the rows of the viewport go. Depth is kept
+            // within as many pixels as the viewport is wide or high.
+            let depth = width.max(height);
+            let projection = cgmath::ortho(0.0, width, height, 0.0, -depth, depth);
+
+            (Matrix4::identity(), view, projection)
+        },
+    };
+
+    // The model matrix is the identity for now, always invertible. Were it
+    // ever degenerate, the normals are left as they are rather than panicking
+    // every frame.
+    let normal_matrix = model.invert().map_or(Matrix4::identity(), |inverse| inverse.transpose());
+
+    let correction = if reversed_depth { reverse_depth() * vulkan_correction() } else { vulkan_correction() };
+
+    vs::ty::Data {
+        view_projection: (correction * projection * view).into(),
+        model: model.into(),
+        normal_matrix: normal_matrix.into(),
+        light_dir: LIGHT_DIR,
+        light_color: LIGHT_COLOR,
+    }
+}
+
+/// Maps depths from 0 to 1 in Vulkan clip space onto 1 to 0
+fn reverse_depth() -> Matrix4<f32> {
+    Matrix4::new(
+        1.0, 0.0,  0.0, 0.0,
+        0.0, 1.0,  0.0, 0.0,
+        0.0, 0.0, -1.0, 0.0,
+        0.0, 0.0,  1.0, 1.0
+    )
+}
+
+/// Maps clip space back to the directions the camera looking along
+/// `camera_direction` sees, through the perspective projection of a viewport of
+/// `viewport_dimensions`. The camera position is left out, the sky being
+/// infinitely far away.
+fn build_sky_matrix(viewport_dimensions: [u32; 2], camera_direction: [f32; 3]) -> [[f32; 4]; 4] {
+    let [width, height] = [viewport_dimensions[0] as f32, viewport_dimensions[1] as f32];
+
+    let rotation =
+        Matrix4::look_at_dir(
+            Point3::new(0.0, 0.0, 0.0), Vector3::from(camera_direction), Vector3::new(0.0, 1.0, 0.0)
+        );
+    let view_projection = vulkan_correction() * perspective_projection(width, height) * rotation;
+
+    // Only degenerate looking straight up or down, which the camera's pitch
+    // limit prevents. The sky would then be left unprojected, not panic.
+    view_projection.invert().unwrap_or_else(Matrix4::identity).into()
+}
+
+/// Perspective projection of a viewport of `width` by `height`. Scales X by the
+/// inverse of the aspect ratio, which keeps shapes from stretching along with
+/// the window.
+pub fn perspective_projection(width: f32, height: f32) -> Matrix4<f32> {
+    cgmath::perspective(Rad(std::f32::consts::FRAC_PI_3), width / height, 0.1, 100.0)
+}
+
+/// cgmath follows OpenGL conventions, while in Vulkan clip space Y points down
+/// and depth goes from 0 to 1 instead of -1 to 1. This converts the former to
+/// the latter.
+pub fn vulkan_correction() -> Matrix4<f32> {
+    Matrix4::new(
+        1.0,  0.0, 0.0, 0.0,
+        0.0, -1.0, 0.0, 0.0,
+        0.0,  0.0, 0.5, 0.0,
+        0.0,  0.0, 0.5, 1.0
+    )
+}
+
+/// Decodes an sRGB encoded color, as color pickers give them, into an opaque
+/// linear one. Attachments with an sRGB format encode it back when cleared, so
+/// that the chosen color is the one displayed.
+fn srgb_to_linear(color: [f32; 3]) -> [f32; 4] {
+    let decode = |c: f32|
+        if c <= 0.04045 { c / 12.92 } else { ((c + 0.055) / 1.055).powf(2.4) };
+
+    [decode(color[0]), decode(color[1]), decode(color[2]), 1.0]
+}
+
+/// A fraction in [0, 1) which looks random, different for each `index` and
+/// `seed`, from the bits of their hash
+fn random_fraction(seed: u32, index: u32) -> f32 {
+    let mut x = seed ^ index.wrapping_mul(0x9e37_79b9);
+    x ^= x >> 16;
+    x = x.wrapping_mul(0x7feb_352d);
+    x ^= x >> 15;
+    x = x.wrapping_mul(0x846c_a68b);
+    x ^= x >> 16;
+
+    // 24 bits are as many as an f32 holds exactly
+    (x >> 8) as f32 / (1 << 24) as f32
+}
+
+/// Converts a hue (wrapping around every 1.0) at full saturation and
+/// brightness into an opaque RGBA color
+fn hue_to_rgba(hue: f32) -> [f32; 4] {
+    let h = hue.fract() * 6.0;
+    let x = 1.0 - (h % 2.0 - 1.0).abs();
+
+    let (r, g, b) = match h as u32 {
+        0 => (1.0, x, 0.0),
+        1 => (x, 1.0, 0.0),
+        2 => (0.0, 1.0, x),
+        3 => (0.0, x, 1.0),
+        4 => (x, 0.0, 1.0),
+        _ => (1.0, 0.0, x),
+    };
+
+    [r, g, b, 1.0]
+}
@@ -0,0 +1,16 @@
+#version 450
+
+// World space, unlike the geometry the lines aren't animated
+layout(location = 0) in vec3 position;
+layout(location = 1) in vec3 color;
+
+layout(location = 0) out vec3 line_color;
+
+layout(push_constant) uniform PushConstants {
+    mat4 view_projection;
+} push_constants;
+
+void main() {
+    gl_Position = push_constants.view_projection * vec4(position, 1.0);
+    line_color = color;
+}
@@ -0,0 +1,14 @@
+#version 450
+
+layout(location = 0) in vec3 frag_color;
+layout(location = 1) in vec3 frag_normal;
+
+// One output per color attachment of the first subpass, in the order it lists
+// them: the albedo, then the world space normal
+layout(location = 0) out vec4 f_albedo;
+layout(location = 1) out vec4 f_normal;
+
+void main() {
+    f_albedo = vec4(frag_color, 1.0);
+    f_normal = vec4(normalize(frag_normal), 0.0);
+}
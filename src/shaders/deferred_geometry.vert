@@ -0,0 +1,20 @@
+#version 450
+
+layout(location = 0) in vec3 position;
+layout(location = 1) in vec3 normal;
+layout(location = 2) in vec3 color;
+
+layout(location = 0) out vec3 frag_color;
+layout(location = 1) out vec3 frag_normal;
+
+layout(push_constant) uniform PushConstants {
+    mat4 view_projection;
+    // Only rotates, so it transforms the normals too
+    mat4 model;
+} push_constants;
+
+void main() {
+    gl_Position = push_constants.view_projection * push_constants.model * vec4(position, 1.0);
+    frag_color = color;
+    frag_normal = mat3(push_constants.model) * normal;
+}
@@ -0,0 +1,30 @@
+#version 450
+
+// The first subpass's outputs, read back at this fragment's own pixel. The
+// index is the attachment's position among the second subpass's inputs.
+layout(input_attachment_index = 0, set = 0, binding = 0) uniform subpassInput g_albedo;
+layout(input_attachment_index = 1, set = 0, binding = 1) uniform subpassInput g_normal;
+
+layout(location = 0) out vec4 f_color;
+
+layout(push_constant) uniform PushConstants {
+    // Direction the light travels in, normalized, in world space
+    vec4 light_dir;
+} push_constants;
+
+// Share of the light reaching the faces turned away from it
+const float AMBIENT = 0.15;
+
+void main() {
+    vec3 albedo = subpassLoad(g_albedo).rgb;
+    vec3 normal = subpassLoad(g_normal).xyz;
+
+    // Cleared to zero where no geometry was drawn, the background is left unlit
+    if (normal == vec3(0.0)) {
+        f_color = vec4(albedo, 1.0);
+        return;
+    }
+
+    float diffuse = max(dot(normal, -push_constants.light_dir.xyz), 0.0);
+    f_color = vec4(albedo * (AMBIENT + (1.0 - AMBIENT) * diffuse), 1.0);
+}
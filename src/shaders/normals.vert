@@ -0,0 +1,48 @@
+#version 450
+
+// Places the geometry like triangle.vert, but in world space: the geometry
+// shader projects it once it has added the normals
+layout(location = 0) in vec3 position;
+layout(location = 3) in vec3 normal;
+
+// Per instance
+layout(location = 4) in vec2 offset;
+layout(location = 5) in float scale;
+
+layout(location = 0) out vec3 world_normal;
+
+layout(set = 0, binding = 0) uniform Data {
+    mat4 view_projection;
+    mat4 model;
+    mat4 normal_matrix;
+    vec4 light_dir;
+    vec4 light_color;
+} uniforms;
+
+layout(push_constant) uniform PushConstants {
+    mat4 object;
+    vec4 color;
+    float time;
+} push_constants;
+
+layout(constant_id = 0) const float offset_x = 0.0;
+layout(constant_id = 1) const float mirror_x = 1.0;
+
+void main() {
+    float c = cos(push_constants.time);
+    float s = sin(push_constants.time);
+    mat4 rotation = mat4(
+        c,   s,   0.0, 0.0,
+        -s,  c,   0.0, 0.0,
+        0.0, 0.0, 1.0, 0.0,
+        0.0, 0.0, 0.0, 1.0
+    );
+
+    vec3 mirror = vec3(mirror_x, 1.0, 1.0);
+    vec3 instance_position = position * mirror * scale + vec3(offset + vec2(offset_x, 0.0), 0.0);
+    gl_Position = rotation * uniforms.model * push_constants.object * vec4(instance_position, 1.0);
+    world_normal =
+        normalize(
+            mat3(rotation) * mat3(uniforms.normal_matrix) * mat3(push_constants.object) * (normal * mirror)
+        );
+}
@@ -0,0 +1,10 @@
+#version 450
+
+// Clip space, already moved by the compute shader
+layout(location = 0) in vec2 pos;
+
+void main() {
+    gl_Position = vec4(pos, 0.0, 1.0);
+    // Clamped to what the device supports, down to a single pixel
+    gl_PointSize = 2.0;
+}
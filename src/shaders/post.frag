@@ -0,0 +1,30 @@
+#version 450
+
+layout(location = 0) in vec2 tex_coord;
+
+// What the first pass drew, the size of the viewport
+layout(set = 0, binding = 0) uniform sampler2D scene;
+
+layout(location = 0) out vec4 f_color;
+
+layout(push_constant) uniform PushConstants {
+    // Half the width of the square of pixels averaged, 0 leaves the scene
+    // sharp
+    int blur_radius;
+} push_constants;
+
+void main() {
+    // Box blur: the average of the (2r + 1)^2 pixels around this one, the
+    // sampler clamping the ones past the edges
+    int radius = push_constants.blur_radius;
+    vec2 texel_size = 1.0 / vec2(textureSize(scene, 0));
+    vec4 sum = vec4(0.0);
+    for (int y = -radius; y <= radius; y++) {
+        for (int x = -radius; x <= radius; x++) {
+            sum += texture(scene, tex_coord + vec2(x, y) * texel_size);
+        }
+    }
+    vec4 color = sum / float((2 * radius + 1) * (2 * radius + 1));
+
+    f_color = vec4(1.0 - color.rgb, color.a);
+}
@@ -0,0 +1,13 @@
+#version 450
+
+// Where the fragment is within the scene image, from 0 to 1
+layout(location = 0) out vec2 frag_tex_coord;
+
+// A single triangle covering the whole screen, its corners are derived from
+// the vertex index so no vertex buffer is needed:
+// 0 -> (-1, -1), 1 -> (3, -1), 2 -> (-1, 3)
+void main() {
+    vec2 position = vec2((gl_VertexIndex << 1) & 2, gl_VertexIndex & 2);
+    gl_Position = vec4(position * 2.0 - 1.0, 0.0, 1.0);
+    frag_tex_coord = position;
+}
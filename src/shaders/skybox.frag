@@ -0,0 +1,20 @@
+#version 450
+
+layout(location = 0) in vec2 clip_position;
+
+layout(location = 0) out vec4 f_color;
+
+layout(set = 0, binding = 0) uniform samplerCube sky;
+
+layout(push_constant) uniform PushConstants {
+    // Undoes the projection and the camera rotation, without its translation:
+    // the sky is infinitely far away
+    mat4 inverse_view_projection;
+} push_constants;
+
+void main() {
+    // Computed per fragment, interpolating directions would bend them
+    vec4 far = push_constants.inverse_view_projection * vec4(clip_position, 1.0, 1.0);
+
+    f_color = texture(sky, far.xyz / far.w);
+}
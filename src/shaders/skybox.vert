@@ -0,0 +1,11 @@
+#version 450
+
+// A triangle covering the whole viewport, derived from the vertex index
+layout(location = 0) out vec2 clip_position;
+
+void main() {
+    vec2 position = vec2((gl_VertexIndex << 1) & 2, gl_VertexIndex & 2) * 2.0 - 1.0;
+
+    clip_position = position;
+    gl_Position = vec4(position, 1.0, 1.0);
+}
@@ -0,0 +1,8 @@
+#version 450
+
+// Only the stencil buffer is written, the pipeline masks the color out
+layout(location = 0) out vec4 f_color;
+
+void main() {
+    f_color = vec4(0.0);
+}
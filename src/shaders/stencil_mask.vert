@@ -0,0 +1,25 @@
+#version 450
+
+// A disc in the middle of the viewport, as a fan of SEGMENTS triangles
+// derived from the vertex index: every third vertex is the center, the two
+// others are consecutive points of the rim
+const int SEGMENTS = 64;
+const float RADIUS = 0.75;
+
+layout(push_constant) uniform PushConstants {
+    // Width over height of the viewport, keeping the disc round
+    float aspect_ratio;
+} push_constants;
+
+void main() {
+    int corner = gl_VertexIndex % 3;
+    int segment = gl_VertexIndex / 3 + (corner == 2 ? 1 : 0);
+
+    vec2 position = vec2(0.0);
+    if (corner != 0) {
+        float angle = 6.28318530718 * float(segment) / float(SEGMENTS);
+        position = RADIUS * vec2(cos(angle) / push_constants.aspect_ratio, sin(angle));
+    }
+
+    gl_Position = vec4(position, 0.0, 1.0);
+}
@@ -0,0 +1,12 @@
+#version 450
+
+layout(location = 0) in vec2 frag_tex_coord;
+
+layout(location = 0) out vec4 f_color;
+
+// White glyphs, covering their pixels as much as their alpha says
+layout(set = 0, binding = 0) uniform sampler2D atlas;
+
+void main() {
+    f_color = texture(atlas, frag_tex_coord);
+}
@@ -0,0 +1,17 @@
+#version 450
+
+// In pixels from the top left corner of the viewport
+layout(location = 0) in vec2 position;
+layout(location = 1) in vec2 tex_coord;
+
+layout(location = 0) out vec2 frag_tex_coord;
+
+layout(push_constant) uniform PushConstants {
+    vec2 viewport_size;
+} push_constants;
+
+void main() {
+    // Clip space Y already points down in Vulkan, like the rows of pixels
+    gl_Position = vec4(position / push_constants.viewport_size * 2.0 - 1.0, 0.0, 1.0);
+    frag_tex_coord = tex_coord;
+}
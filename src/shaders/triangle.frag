@@ -0,0 +1,55 @@
+#version 450
+
+layout(location = 0) in vec2 tex_coord;
+// Interpolated between the vertices of the triangle
+layout(location = 1) in vec4 color;
+layout(location = 2) in vec3 normal;
+layout(location = 3) in vec3 tangent;
+
+layout(location = 0) out vec4 f_color;
+
+layout(set = 0, binding = 0) uniform Data {
+    mat4 view_projection;
+    mat4 model;
+    mat4 normal_matrix;
+    // Towards the light, in world space
+    vec4 light_dir;
+    vec4 light_color;
+} uniforms;
+
+layout(set = 0, binding = 1) uniform sampler2D tex;
+// Directions relative to the surface: X along the tangent, Y along the
+// bitangent and Z along the normal, stored in [0, 1]
+layout(set = 0, binding = 2) uniform sampler2D normal_map;
+
+// Set per pipeline, baked in when it's created. Specialization constants
+// can't be vectors, hence one per channel.
+layout(constant_id = 0) const float base_r = 1.0;
+layout(constant_id = 1) const float base_g = 1.0;
+layout(constant_id = 2) const float base_b = 1.0;
+
+// 84 bytes: 64 for the matrix, 16 for the color and 4 for the time. Within
+// the 128 bytes of push constants every device supports, leaving 44 for more.
+layout(push_constant) uniform PushConstants {
+    mat4 object;
+    vec4 color;
+    float time;
+} push_constants;
+
+void main() {
+    // The texture is tinted by the vertex colors, the base color, then the
+    // animated one
+    vec4 tint = color * vec4(base_r, base_g, base_b, 1.0) * push_constants.color;
+    vec4 albedo = texture(tex, tex_coord) * tint;
+
+    // Interpolation shortens the normals, hence normalizing again, and can
+    // tilt the tangent off the surface, which Gram-Schmidt straightens
+    vec3 n = normalize(normal);
+    vec3 t = normalize(tangent - dot(tangent, n) * n);
+    mat3 tbn = mat3(t, cross(n, t), n);
+    vec3 mapped_normal = normalize(tbn * (texture(normal_map, tex_coord).xyz * 2.0 - 1.0));
+
+    // Lambert: surfaces are lit by how squarely they face the light
+    float diffuse = max(dot(mapped_normal, normalize(uniforms.light_dir.xyz)), 0.0);
+    f_color = vec4(albedo.rgb * uniforms.light_color.rgb * diffuse, albedo.a);
+}
@@ -0,0 +1,75 @@
+#version 450
+
+layout(location = 0) in vec3 position;
+layout(location = 1) in vec2 tex_coord;
+layout(location = 2) in vec4 color;
+layout(location = 3) in vec3 normal;
+layout(location = 4) in vec3 tangent;
+
+// Per instance
+layout(location = 5) in vec2 offset;
+layout(location = 6) in float scale;
+layout(location = 7) in vec4 instance_color;
+
+layout(location = 0) out vec2 frag_tex_coord;
+layout(location = 1) out vec4 frag_color;
+layout(location = 2) out vec3 frag_normal;
+layout(location = 3) out vec3 frag_tangent;
+
+// Shared with the fragment shader, which lights the geometry
+layout(set = 0, binding = 0) uniform Data {
+    mat4 view_projection;
+    // Placement of the geometry, before it's spun
+    mat4 model;
+    // Inverse transpose of the model matrix, keeping normals perpendicular to
+    // the surface even under non-uniform scaling
+    mat4 normal_matrix;
+    vec4 light_dir;
+    vec4 light_color;
+} uniforms;
+
+// Same block as the fragment shader's, each stage reads the members it needs
+layout(push_constant) uniform PushConstants {
+    // Placement of the object drawn within the scene, before the model
+    // matrix. A rotation and a uniform scale, so it also turns the normals.
+    mat4 object;
+    vec4 color;
+    // Seconds since the animation started
+    float time;
+} push_constants;
+
+// Set per pipeline, so that several copies of the geometry sit side by side
+layout(constant_id = 0) const float offset_x = 0.0;
+// Only read when the vertices are drawn as points, clamped to what the device
+// supports
+layout(constant_id = 1) const float point_size = 1.0;
+// -1 mirrors the geometry along X, which also reverses the winding of its
+// triangles
+layout(constant_id = 2) const float mirror_x = 1.0;
+
+void main() {
+    // Spins around the Z axis, one radian per second
+    float c = cos(push_constants.time);
+    float s = sin(push_constants.time);
+    mat4 rotation = mat4(
+        c,   s,   0.0, 0.0,
+        -s,  c,   0.0, 0.0,
+        0.0, 0.0, 1.0, 0.0,
+        0.0, 0.0, 0.0, 1.0
+    );
+
+    vec3 mirror = vec3(mirror_x, 1.0, 1.0);
+    vec3 instance_position = position * mirror * scale + vec3(offset + vec2(offset_x, 0.0), 0.0);
+    gl_Position =
+        uniforms.view_projection * rotation * uniforms.model * push_constants.object
+            * vec4(instance_position, 1.0);
+    gl_PointSize = point_size;
+    frag_tex_coord = tex_coord;
+    frag_color = color * instance_color;
+    // A rotation is its own inverse transpose
+    // and so is the mirror
+    frag_normal =
+        mat3(rotation) * mat3(uniforms.normal_matrix) * mat3(push_constants.object) * (normal * mirror);
+    // Tangents lie along the surface, they follow the model matrix itself
+    frag_tangent = mat3(rotation) * mat3(uniforms.model) * mat3(push_constants.object) * (tangent * mirror);
+}
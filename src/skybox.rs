@@ -0,0 +1,147 @@
+// Build-in modules
+use std::sync::Arc;
+
+// External modules
+use vulkano::command_buffer::AutoCommandBufferBuilder;
+use vulkano::command_buffer::DynamicState;
+use vulkano::descriptor::DescriptorSet;
+use vulkano::descriptor::descriptor_set::PersistentDescriptorSet;
+use vulkano::device::{Device, Queue};
+use vulkano::format::Format;
+use vulkano::framebuffer::RenderPassAbstract;
+use vulkano::framebuffer::Subpass;
+use vulkano::image::Dimensions;
+use vulkano::image::ImmutableImage;
+use vulkano::pipeline::GraphicsPipeline;
+use vulkano::pipeline::vertex::BufferlessVertices;
+use vulkano::sampler::{Filter, MipmapMode, Sampler, SamplerAddressMode};
+
+// Internal modules
+use crate::error::AppError;
+use crate::scene::{BufferlessPipeline, UploadFuture};
+
+/// Faces of a cubemap, in the order Vulkan stores them as array layers
+pub const FACE_NAMES: [&str; 6] = ["+X", "-X", "+Y", "-Y", "+Z", "-Z"];
+
+mod vs {
+    vulkano_shaders::shader!{
+        ty: "vertex",
+        path: "src/shaders/skybox.vert"
+    }
+}
+
+mod fs {
+    vulkano_shaders::shader!{
+        ty: "fragment",
+        path: "src/shaders/skybox.frag"
+    }
+}
+
+/// Surrounds the scene with a cubemap, sampled along the direction each pixel
+/// is seen in. Drawn as a single triangle covering the viewport rather than a
+/// cube, which needs no vertices.
+pub struct Skybox {
+    pipeline: Arc<BufferlessPipeline>,
+    set: Arc<dyn DescriptorSet + Send + Sync>,
+}
+
+impl Skybox {
+    /// Loads the PNGs at `face_paths`, one per face in the order of
+    /// `FACE_NAMES`, which must all be square and of the same size. Returns
+    /// the skybox drawn in the subpass `subpass` of `render_pass`, along with
+    /// the upload of its faces through `queue`.
+    pub fn new(
+        device: Arc<Device>,
+        queue: Arc<Queue>,
+        render_pass: &Arc<dyn RenderPassAbstract + Send + Sync>,
+        subpass: u32,
+        face_paths: &[String]
+    ) -> Result<(Self, UploadFuture), AppError> {
+        if face_paths.len() != FACE_NAMES.len() {
+            return Err(AppError::InvalidArgument(
+                format!("a skybox needs {} faces, got {}", FACE_NAMES.len(), face_paths.len())
+            ));
+        }
+
+        // The layers of a cubemap are uploaded from a single buffer, one face
+        // after the other
+        let mut size = None;
+        let mut pixels = Vec::new();
+        for (path, name) in face_paths.iter().zip(&FACE_NAMES) {
+            let face = image::open(path)?.to_rgba();
+            let (width, height) = face.dimensions();
+
+            if width != height {
+                return Err(AppError::InvalidArgument(
+                    format!("the {} skybox face {} is {}x{}, not square", name, path, width, height)
+                ));
+            }
+            if size.is_some_and(|size| size != width) {
+                return Err(AppError::InvalidArgument(
+                    format!("the {} skybox face {} isn't the size of the first face", name, path)
+                ));
+            }
+
+            size = Some(width);
+            pixels.extend(face.into_raw());
+        }
+        let size = size.expect("there are six faces");
+
+        let (cubemap, upload) =
+            ImmutableImage::from_iter(
+                pixels.into_iter(), Dimensions::Cubemap { size }, Format::R8G8B8A8Srgb, queue
+            )?;
+
+        // Clamped, repeating would blend the edges of a face with its opposite
+        let sampler =
+            Sampler::new(
+                device.clone(),
+                Filter::Linear, Filter::Linear, MipmapMode::Nearest,
+                SamplerAddressMode::ClampToEdge, SamplerAddressMode::ClampToEdge,
+                SamplerAddressMode::ClampToEdge,
+                0.0, 1.0, 0.0, 0.0
+            )?;
+
+        let vs = vs::Shader::load(device.clone())?;
+        let fs = fs::Shader::load(device.clone())?;
+
+        // Without any depth test or write, whatever is drawn next covers it
+        let pipeline =
+            Arc::new(
+                GraphicsPipeline::start()
+                    .vertex_shader(vs.main_entry_point(), ())
+                    .viewports_scissors_dynamic(1)
+                    .fragment_shader(fs.main_entry_point(), ())
+                    .render_pass(
+                        Subpass::from(render_pass.clone(), subpass)
+                            .ok_or(AppError::MissingSubpass(subpass))?
+                    )
+                    .build(device)?
+            );
+
+        let set =
+            Arc::new(
+                PersistentDescriptorSet::start(pipeline.clone(), 0)
+                    .add_sampled_image(cubemap, sampler)?
+                    .build()?
+            );
+
+        Ok((Skybox { pipeline, set }, Box::new(upload)))
+    }
+
+    /// Records the draw of the sky seen through `inverse_view_projection`,
+    /// which must map clip space back to directions in world space, within the
+    /// subpass it was prepared for
+    pub fn draw(
+        &self,
+        builder: AutoCommandBufferBuilder,
+        dynamic_state: &DynamicState,
+        inverse_view_projection: [[f32; 4]; 4]
+    ) -> Result<AutoCommandBufferBuilder, AppError> {
+        Ok(builder.draw(
+            self.pipeline.clone(), dynamic_state,
+            BufferlessVertices { vertices: 3, instances: 1 }, self.set.clone(),
+            fs::ty::PushConstants { inverse_view_projection }
+        )?)
+    }
+}
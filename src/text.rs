@@ -0,0 +1,194 @@
+// Build-in modules
+use std::sync::Arc;
+
+// External modules
+use vulkano::buffer::BufferUsage;
+use vulkano::buffer::CpuAccessibleBuffer;
+use vulkano::command_buffer::AutoCommandBufferBuilder;
+use vulkano::command_buffer::DynamicState;
+use vulkano::descriptor::DescriptorSet;
+use vulkano::descriptor::descriptor_set::PersistentDescriptorSet;
+use vulkano::device::Device;
+use vulkano::format::Format;
+use vulkano::framebuffer::RenderPassAbstract;
+use vulkano::framebuffer::Subpass;
+use vulkano::image::ImmutableImage;
+use vulkano::pipeline::GraphicsPipeline;
+use vulkano::pipeline::GraphicsPipelineAbstract;
+use vulkano::pipeline::blend::AttachmentBlend;
+use vulkano::pipeline::vertex::SingleBufferDefinition;
+use vulkano::sampler::{Filter, MipmapMode, Sampler, SamplerAddressMode};
+
+// Internal modules
+use crate::error::AppError;
+
+/// Size of a glyph in the atlas, in pixels. Every glyph is as wide as the
+/// font advances by: it's monospace.
+const GLYPH_WIDTH: f32 = 8.0;
+const GLYPH_HEIGHT: f32 = 16.0;
+
+/// The atlas has the printable ASCII characters, from the space on, in rows
+/// of `ATLAS_COLUMNS`
+const FIRST_GLYPH: char = ' ';
+const LAST_GLYPH: char = '~';
+const ATLAS_COLUMNS: u32 = 16;
+const ATLAS_ROWS: u32 = 6;
+
+/// Drawn for the characters the atlas doesn't have
+const MISSING_GLYPH: char = '?';
+
+/// One corner of a glyph's quad
+#[derive(Default, Copy, Clone)]
+struct TextVertex {
+    /// In pixels from the top left corner of the viewport
+    position: [f32; 2],
+    tex_coord: [f32; 2],
+}
+vulkano::impl_vertex!(TextVertex, position, tex_coord);
+
+mod vs {
+    vulkano_shaders::shader!{
+        ty: "vertex",
+        path: "src/shaders/text.vert"
+    }
+}
+
+mod fs {
+    vulkano_shaders::shader!{
+        ty: "fragment",
+        path: "src/shaders/text.frag"
+    }
+}
+
+/// Collects text during a frame, to draw it over everything else with a
+/// monospace bitmap font. Meant for overlays such as the frame rate.
+pub struct TextRenderer {
+    device: Arc<Device>,
+    pipeline: Arc<dyn GraphicsPipelineAbstract + Send + Sync>,
+    set: Arc<dyn DescriptorSet + Send + Sync>,
+    /// Six per glyph, two triangles
+    vertices: Vec<TextVertex>,
+}
+
+impl TextRenderer {
+    /// Prepares drawing text with the glyphs of `atlas` in the subpass
+    /// `subpass` of `render_pass`
+    pub fn new(
+        device: Arc<Device>,
+        render_pass: &Arc<dyn RenderPassAbstract + Send + Sync>,
+        subpass: u32,
+        atlas: Arc<ImmutableImage<Format>>
+    ) -> Result<Self, AppError> {
+        let vs = vs::Shader::load(device.clone())?;
+        let fs = fs::Shader::load(device.clone())?;
+
+        let pipeline =
+            Arc::new(
+                GraphicsPipeline::start()
+                    .vertex_input(SingleBufferDefinition::<TextVertex>::new())
+                    .vertex_shader(vs.main_entry_point(), ())
+                    .triangle_list()
+                    .viewports_scissors_dynamic(1)
+                    .fragment_shader(fs.main_entry_point(), ())
+                    .blend_collective(AttachmentBlend::alpha_blending())
+                    .render_pass(
+                        Subpass::from(render_pass.clone(), subpass)
+                            .ok_or(AppError::MissingSubpass(subpass))?
+                    )
+                    .build(device.clone())?
+            );
+
+        // Drawn pixel for pixel, any filtering would blur the glyphs
+        let sampler =
+            Sampler::new(
+                device.clone(),
+                Filter::Nearest, Filter::Nearest, MipmapMode::Nearest,
+                SamplerAddressMode::ClampToEdge, SamplerAddressMode::ClampToEdge,
+                SamplerAddressMode::ClampToEdge,
+                0.0, 1.0, 0.0, 0.0
+            )?;
+
+        let set =
+            Arc::new(
+                PersistentDescriptorSet::start(pipeline.clone(), 0)
+                    .add_sampled_image(atlas, sampler)?
+                    .build()?
+            );
+
+        Ok(TextRenderer { device, pipeline, set, vertices: Vec::new() })
+    }
+
+    /// Draws `text` with its top left corner `x` and `y` pixels away from the
+    /// viewport's. Each new line starts back at `x`, one glyph lower.
+    pub fn draw_text(&mut self, text: &str, x: f32, y: f32) {
+        let (mut left, mut top) = (x, y);
+
+        for character in text.chars() {
+            if character == '\n' {
+                left = x;
+                top += GLYPH_HEIGHT;
+                continue;
+            }
+
+            if character != ' ' {
+                self.add_glyph(character, left, top);
+            }
+            left += GLYPH_WIDTH;
+        }
+    }
+
+    /// Forgets all the text added so far, which is done once per frame
+    pub fn clear(&mut self) {
+        self.vertices.clear();
+    }
+
+    /// Records the draw of the text added so far into a viewport of
+    /// `viewport_dimensions`, within the subpass it was prepared for
+    pub fn draw(
+        &self,
+        builder: AutoCommandBufferBuilder,
+        dynamic_state: &DynamicState,
+        viewport_dimensions: [u32; 2]
+    ) -> Result<AutoCommandBufferBuilder, AppError> {
+        // An empty buffer can't be created
+        if self.vertices.is_empty() {
+            return Ok(builder);
+        }
+
+        let vertex_buffer =
+            CpuAccessibleBuffer::from_iter(
+                self.device.clone(), BufferUsage::vertex_buffer(), self.vertices.iter().cloned()
+            )?;
+
+        let viewport_size = [viewport_dimensions[0] as f32, viewport_dimensions[1] as f32];
+
+        Ok(builder.draw(
+            self.pipeline.clone(), dynamic_state, vec![vertex_buffer], self.set.clone(),
+            vs::ty::PushConstants { viewport_size }
+        )?)
+    }
+
+    /// Adds the quad of `character` with its top left corner at `left` and
+    /// `top`
+    fn add_glyph(&mut self, character: char, left: f32, top: f32) {
+        let character =
+            if (FIRST_GLYPH ..= LAST_GLYPH).contains(&character) { character } else { MISSING_GLYPH };
+        let index = character as u32 - FIRST_GLYPH as u32;
+
+        let (u, v) = ((index % ATLAS_COLUMNS) as f32, (index / ATLAS_COLUMNS) as f32);
+        let (u_min, u_max) = (u / ATLAS_COLUMNS as f32, (u + 1.0) / ATLAS_COLUMNS as f32);
+        let (v_min, v_max) = (v / ATLAS_ROWS as f32, (v + 1.0) / ATLAS_ROWS as f32);
+
+        let (right, bottom) = (left + GLYPH_WIDTH, top + GLYPH_HEIGHT);
+        let corner = |x: f32, y: f32, u: f32, v: f32| TextVertex { position: [x, y], tex_coord: [u, v] };
+
+        self.vertices.extend_from_slice(&[
+            corner(left, top, u_min, v_min),
+            corner(right, top, u_max, v_min),
+            corner(left, bottom, u_min, v_max),
+            corner(right, top, u_max, v_min),
+            corner(right, bottom, u_max, v_max),
+            corner(left, bottom, u_min, v_max),
+        ]);
+    }
+}
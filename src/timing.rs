@@ -0,0 +1,84 @@
+// Build-in modules
+use std::collections::VecDeque;
+use std::time::{Duration, Instant};
+
+/// Length of the window the FPS are averaged over
+const FPS_WINDOW: Duration = Duration::from_secs(1);
+
+/// Measures the time between frames and the frame rate over the last second,
+/// along with how long the CPU spends on each
+pub struct FrameTimer {
+    last_frame: Instant,
+    /// When each frame of the last `FPS_WINDOW` started, oldest first
+    frame_starts: VecDeque<Instant>,
+    /// When each CPU time of the last `FPS_WINDOW` was recorded, and its value
+    cpu_times: VecDeque<(Instant, Duration)>,
+}
+
+impl FrameTimer {
+    pub fn new() -> Self {
+        FrameTimer {
+            last_frame: Instant::now(),
+            frame_starts: VecDeque::new(),
+            cpu_times: VecDeque::new(),
+        }
+    }
+
+    /// Marks the start of a new frame and returns the time elapsed since the
+    /// previous one, in seconds
+    pub fn tick(&mut self) -> f32 {
+        let now = Instant::now();
+        let delta_time = now.duration_since(self.last_frame).as_secs_f32();
+        self.last_frame = now;
+
+        self.frame_starts.push_back(now);
+        while let Some(&oldest) = self.frame_starts.front() {
+            if now.duration_since(oldest) <= FPS_WINDOW {
+                break;
+            }
+            self.frame_starts.pop_front();
+        }
+
+        delta_time
+    }
+
+    /// Average frame rate over the last second
+    pub fn fps(&self) -> f32 {
+        match (self.frame_starts.front(), self.frame_starts.back()) {
+            (Some(&oldest), Some(&newest)) if newest > oldest => {
+                let span = newest.duration_since(oldest).as_secs_f32();
+                (self.frame_starts.len() - 1) as f32 / span
+            },
+            _ => 0.0,
+        }
+    }
+
+    /// Records how long the CPU took to prepare and submit the current frame
+    pub fn record_cpu_time(&mut self, cpu_time: Duration) {
+        let now = Instant::now();
+
+        self.cpu_times.push_back((now, cpu_time));
+        while let Some(&(oldest, _)) = self.cpu_times.front() {
+            if now.duration_since(oldest) <= FPS_WINDOW {
+                break;
+            }
+            self.cpu_times.pop_front();
+        }
+    }
+
+    /// Average CPU time over the last second, in milliseconds
+    pub fn cpu_time(&self) -> Option<f32> {
+        if self.cpu_times.is_empty() {
+            return None;
+        }
+
+        let total = self.cpu_times.iter().map(|&(_, cpu_time)| cpu_time).sum::<Duration>();
+        Some(total.as_secs_f32() * 1000.0 / self.cpu_times.len() as f32)
+    }
+}
+
+impl Default for FrameTimer {
+    fn default() -> Self {
+        FrameTimer::new()
+    }
+}
@@ -0,0 +1,270 @@
+// Build-in modules
+use std::sync::Arc;
+use std::time::Instant;
+
+// External modules
+use vulkano::device::{Device, DeviceExtensions, Queue};
+use vulkano::format::Format;
+use vulkano::image::swapchain::SwapchainImage;
+use vulkano::swapchain::AcquireError;
+use vulkano::swapchain::PresentMode;
+use vulkano::swapchain::Surface;
+use vulkano::swapchain::Swapchain;
+use vulkano::swapchain::SwapchainCreationError;
+use vulkano::swapchain;
+use vulkano::sync::FlushError;
+use vulkano::sync::GpuFuture;
+use vulkano::sync;
+use vulkano_win::VkSurfaceBuild;
+use winit::ElementState;
+use winit::EventsLoop;
+use winit::KeyboardInput;
+use winit::VirtualKeyCode;
+use winit::Window;
+use winit::WindowBuilder;
+use winit::WindowId;
+
+// Internal modules
+use crate::config::Config;
+use crate::error::AppError;
+use crate::renderer::{self, FrameFence, SwapchainUsage, VulkanContext};
+use crate::scene::{Scene, Targets};
+
+/// Number of windows opened
+const WINDOW_COUNT: usize = 2;
+
+/// One window the scene is drawn into, with its own swapchain and the
+/// framebuffers drawing into its images
+struct Output {
+    surface: Arc<Surface<Window>>,
+    swapchain: Arc<Swapchain<Window>>,
+    images: Vec<Arc<SwapchainImage<Window>>>,
+    targets: Targets,
+    /// Fence of the last frame rendered into each swapchain image
+    fences: Vec<Option<FrameFence>>,
+    previous_fence_index: usize,
+    /// Set when the swapchain no longer matches the window
+    swapchain_outdated: bool,
+}
+
+impl Output {
+    fn new(
+        device: &Arc<Device>,
+        queue: &Arc<Queue>,
+        surface: Arc<Surface<Window>>,
+        scene: &Scene,
+        format: Format,
+        present_mode: Option<PresentMode>,
+        image_count: Option<u32>
+    ) -> Result<Self, AppError> {
+        let (swapchain, images) =
+            renderer::create_swapchain(
+                device, queue, surface.clone(), format, present_mode, image_count, SwapchainUsage::draw()
+            )?;
+        let targets = scene.create_targets(&images)?;
+
+        Ok(Output {
+            surface,
+            swapchain,
+            fences: vec![None; images.len()],
+            images,
+            targets,
+            previous_fence_index: 0,
+            swapchain_outdated: false,
+        })
+    }
+
+    /// Recreates the swapchain and the targets at the window's size, retried
+    /// on the next frame in the middle of a resize
+    fn recreate_swapchain(&mut self, device: &Arc<Device>, scene: &Scene) -> Result<(), AppError> {
+        self.swapchain_outdated = true;
+
+        let capabilities = self.surface.capabilities(device.physical_device())?;
+        let dimensions = renderer::get_dimensions(&self.surface, &capabilities);
+
+        let (swapchain, images) =
+            match self.swapchain.recreate_with_dimension(dimensions) {
+                Ok(r) => r,
+                Err(SwapchainCreationError::UnsupportedDimensions) => return Ok(()),
+                Err(err) => return Err(err.into()),
+            };
+
+        self.swapchain = swapchain;
+        self.images = images;
+        self.fences.resize(self.images.len(), None);
+        self.previous_fence_index = self.previous_fence_index.min(self.images.len() - 1);
+        self.targets = scene.create_targets(&self.images)?;
+
+        self.swapchain_outdated = false;
+
+        Ok(())
+    }
+
+    /// Acquires an image of the swapchain, draws the scene as it is `time`
+    /// seconds into the animation into it and presents it, after `before`
+    fn draw_frame(
+        &mut self,
+        device: &Arc<Device>,
+        queue: &Arc<Queue>,
+        scene: &Scene,
+        time: f32,
+        before: Option<Box<dyn GpuFuture + Send + Sync>>
+    ) -> Result<(), AppError> {
+        if self.swapchain_outdated {
+            self.recreate_swapchain(device, scene)?;
+
+            if self.swapchain_outdated {
+                return Ok(());
+            }
+        }
+
+        let (image_num, acquire_future) =
+            match swapchain::acquire_next_image(self.swapchain.clone(), None) {
+                Ok(r) => r,
+                Err(AcquireError::OutOfDate) => {
+                    self.swapchain_outdated = true;
+                    return Ok(());
+                },
+                Err(err) => return Err(err.into()),
+            };
+
+        if let Some(image_fence) = &self.fences[image_num] {
+            image_fence.wait(None)?;
+        }
+
+        let previous_future = match self.fences[self.previous_fence_index].clone() {
+            Some(fence) => Box::new(fence) as Box<dyn GpuFuture + Send + Sync>,
+            None => {
+                let mut now = sync::now(device.clone());
+                now.cleanup_finished();
+
+                Box::new(now) as Box<dyn GpuFuture + Send + Sync>
+            },
+        };
+        let previous_future = match before {
+            Some(before) => Box::new(previous_future.join(before)),
+            None => previous_future,
+        };
+
+        let command_buffer = scene.draw(&mut self.targets, image_num, time)?;
+
+        let future =
+            Box::new(
+                previous_future
+                    .join(acquire_future)
+                    .then_execute(queue.clone(), command_buffer)?
+                    .then_swapchain_present(queue.clone(), self.swapchain.clone(), image_num)
+            ) as Box<dyn GpuFuture + Send + Sync>;
+
+        self.fences[image_num] = match future.then_signal_fence_and_flush() {
+            Ok(future) => {
+                let fence = Arc::new(future);
+                self.targets.track_frame(&fence);
+
+                Some(fence)
+            },
+            Err(FlushError::OutOfDate) => {
+                self.swapchain_outdated = true;
+                None
+            },
+            Err(err) => return Err(err.into()),
+        };
+        self.previous_fence_index = image_num;
+
+        Ok(())
+    }
+}
+
+/// Draws the scene `config` describes into two windows at once, each with its
+/// own surface and swapchain but sharing the device and the scene. Closing a
+/// window only closes that one, the program exits once both are.
+pub fn run(config: &Config) -> Result<(), AppError> {
+    let context = VulkanContext::new(vulkano_win::required_extensions())?;
+    let extensions = DeviceExtensions { khr_swapchain: true, .. DeviceExtensions::none() };
+    let (device, queue, transfer_queue) = context.open_scene_device(config, &extensions)?;
+    let physical_device = device.physical_device();
+
+    let mut events_loop = EventsLoop::new();
+    let surfaces =
+        (0 .. WINDOW_COUNT).map(|index| {
+            Ok(WindowBuilder::new()
+                .with_dimensions((config.width, config.height).into())
+                .with_title(format!("{} - window {}", config.title, index + 1))
+                .build_vk_surface(&events_loop, context.instance.clone())?)
+        }).collect::<Result<Vec<_>, AppError>>()?;
+
+    // The post-processing pipeline is built for a single format, which every
+    // swapchain must then use
+    let format =
+        renderer::choose_swapchain_format(
+            &surfaces[0].capabilities(physical_device)?, config.swapchain_encoding
+        );
+    for surface in &surfaces[1 ..] {
+        let capabilities = surface.capabilities(physical_device)?;
+        if !capabilities.supported_formats.iter().any(|&(supported, _)| supported == format) {
+            return Err(AppError::NoSuitableDevice(format!("not every window supports {:?}", format)));
+        }
+    }
+
+    let mut scene = Scene::from_config(device.clone(), queue.clone(), transfer_queue, format, config)?;
+
+    let mut outputs =
+        surfaces.into_iter()
+            .map(|surface| {
+                let id = surface.window().id();
+                let output =
+                    Output::new(
+                        &device, &queue, surface, &scene, format, config.present_mode, config.image_count
+                    )?;
+                Ok((id, output))
+            })
+            .collect::<Result<Vec<(WindowId, Output)>, AppError>>()?;
+
+    let start_time = Instant::now();
+    let mut last_time = 0.0;
+
+    while !outputs.is_empty() {
+        let time = start_time.elapsed().as_secs_f32();
+        scene.update(time - last_time);
+        last_time = time;
+
+        // Only the first frame drawn waits for the uploads, the next ones
+        // already wait for it
+        let mut uploads = scene.take_pending_uploads();
+        for (_, output) in &mut outputs {
+            output.draw_frame(&device, &queue, &scene, time, uploads.take())?;
+        }
+
+        let mut closed = Vec::new();
+        events_loop.poll_events(|event| {
+            let (window_id, event) = match event {
+                winit::Event::WindowEvent { window_id, event } => (window_id, event),
+                _ => return,
+            };
+
+            match event {
+                winit::WindowEvent::CloseRequested
+                | winit::WindowEvent::KeyboardInput {
+                    input: KeyboardInput {
+                        state: ElementState::Pressed, virtual_keycode: Some(VirtualKeyCode::Escape), ..
+                    },
+                    ..
+                } => closed.push(window_id),
+                // A new DPI factor changes the physical size the swapchain
+                // must have, even when the logical one stays the same
+                winit::WindowEvent::Resized(_) | winit::WindowEvent::HiDpiFactorChanged(_) => {
+                    if let Some((_, output)) = outputs.iter_mut().find(|(id, _)| *id == window_id) {
+                        output.swapchain_outdated = true;
+                    }
+                },
+                _ => (),
+            }
+        });
+
+        // Dropping an output waits for its frames in flight, then closes its
+        // window along with its surface
+        outputs.retain(|(id, _)| !closed.contains(id));
+    }
+
+    Ok(())
+}
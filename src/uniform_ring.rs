@@ -0,0 +1,234 @@
+// Build-in modules
+use std::iter;
+use std::marker::PhantomData;
+use std::mem;
+use std::ops::Range;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+// External modules
+use vulkano::buffer::BufferAccess;
+use vulkano::buffer::BufferInner;
+use vulkano::buffer::BufferUsage;
+use vulkano::buffer::TypedBufferAccess;
+use vulkano::buffer::cpu_access::WriteLockError;
+use vulkano::buffer::sys::{SparseLevel, UnsafeBuffer};
+use vulkano::device::{Device, DeviceOwned, Queue};
+use vulkano::image::ImageAccess;
+use vulkano::memory::{DeviceMemory, MappedDeviceMemory};
+use vulkano::sync::AccessError;
+use vulkano::sync::Sharing;
+
+// Internal modules
+use crate::error::AppError;
+use crate::renderer::FrameFence;
+
+/// Streams one `T` of uniforms per frame through a single host visible
+/// buffer, mapped once for good and split into slots used in turn as a ring.
+/// Each frame writes into the next slot, which is only overwritten once the
+/// frame that last read it, whose fence `track` was given, is done.
+///
+/// vulkano 0.13 always binds descriptor sets without dynamic offsets, so
+/// rather than a single set bound at each slot's offset, the slots get a set
+/// of their own, built once over their range of the buffer.
+pub struct UniformRing<T> {
+    slots: Vec<Arc<RingSlot<T>>>,
+    /// Bytes from the start of a slot to the next, a multiple of the offset
+    /// alignment uniform buffers need
+    slot_size: usize,
+    /// Fence of the last frame reading each slot, `None` when none is in
+    /// flight
+    fences: Vec<Option<FrameFence>>,
+    next_slot: usize,
+}
+
+impl<T: Copy + Send + Sync + 'static> UniformRing<T> {
+    /// Allocates `slot_count` slots of uniforms, as many frames as can be in
+    /// flight at once without waiting for one
+    pub fn new(device: Arc<Device>, slot_count: usize) -> Result<Self, AppError> {
+        let alignment = device.physical_device().limits().min_uniform_buffer_offset_alignment() as usize;
+        let slot_size = mem::size_of::<T>().div_ceil(alignment) * alignment;
+
+        let (buffer, requirements) = unsafe {
+            UnsafeBuffer::new(
+                device.clone(), slot_count * slot_size, BufferUsage::uniform_buffer(),
+                Sharing::Exclusive::<iter::Empty<u32>>, SparseLevel::none()
+            )?
+        };
+
+        // Coherent memory needs no flushing, which could otherwise spill over
+        // the neighbouring slots to the atom size of the flushes
+        let memory_type =
+            device.physical_device().memory_types()
+                .filter(|memory_type| requirements.memory_type_bits & (1 << memory_type.id()) != 0)
+                .find(|memory_type| memory_type.is_host_visible() && memory_type.is_host_coherent())
+                .ok_or(AppError::NoHostCoherentMemory)?;
+        let memory = DeviceMemory::alloc_and_map(device.clone(), memory_type, requirements.size)?;
+        unsafe { buffer.bind_memory(memory.as_ref(), 0)?; }
+
+        let ring = Arc::new(RingMemory { buffer, memory });
+        let slots =
+            (0 .. slot_count)
+                .map(|slot| Arc::new(RingSlot {
+                    ring: ring.clone(),
+                    offset: slot * slot_size,
+                    gpu_reads: AtomicUsize::new(0),
+                    _content: PhantomData,
+                }))
+                .collect();
+
+        Ok(UniformRing {
+            slots,
+            slot_size,
+            fences: vec![None; slot_count],
+            next_slot: 0,
+        })
+    }
+
+    /// Writes `data` into the next slot, once the last frame reading it is
+    /// done, and returns the offset of that slot in bytes
+    pub fn write_uniform(&mut self, data: T) -> Result<usize, AppError> {
+        let slot = self.next_slot;
+        self.next_slot = (slot + 1) % self.fences.len();
+
+        // Waiting also unlocks the slot from the command buffers of the frame
+        if let Some(fence) = self.fences[slot].take() {
+            fence.wait(None)?;
+        }
+
+        self.slots[slot].write(data)?;
+
+        Ok(slot * self.slot_size)
+    }
+
+    /// Keeps the slot at `offset` from being written into again until `fence`,
+    /// that of the frame reading it, is signaled
+    pub fn track(&mut self, offset: usize, fence: FrameFence) {
+        self.fences[offset / self.slot_size] = Some(fence);
+    }
+
+    /// Each slot, in order, to bind as uniform buffers
+    pub fn slots(&self) -> Vec<Arc<RingSlot<T>>> {
+        self.slots.clone()
+    }
+
+    /// Leaks the fences of the frames still reading the slots, which can't be
+    /// waited for once the device is lost
+    pub fn forget_fences(&mut self) {
+        for fence in self.fences.iter_mut().filter_map(Option::take) {
+            mem::forget(fence);
+        }
+    }
+
+    /// Index among `slots` of the slot at `offset`
+    pub fn slot_index(&self, offset: usize) -> usize {
+        offset / self.slot_size
+    }
+}
+
+/// The buffer of a `UniformRing` along with the memory it's bound to
+struct RingMemory {
+    buffer: UnsafeBuffer,
+    memory: MappedDeviceMemory,
+}
+
+/// Value of `RingSlot::gpu_reads` while the CPU writes into the slot
+const CPU_WRITING: usize = usize::MAX;
+
+/// One slot of a `UniformRing`, locked on its own so that the CPU can write
+/// into a slot while the GPU reads the others, which vulkano's buffers,
+/// locked as a whole, wouldn't allow
+pub struct RingSlot<T> {
+    ring: Arc<RingMemory>,
+    /// Bytes from the start of the buffer
+    offset: usize,
+    /// Command buffers in flight reading the slot, or `CPU_WRITING`
+    gpu_reads: AtomicUsize,
+    _content: PhantomData<T>,
+}
+
+impl<T: Copy + Send + Sync + 'static> RingSlot<T> {
+    fn range(&self) -> Range<usize> {
+        self.offset .. self.offset + mem::size_of::<T>()
+    }
+
+    /// Writes `data` into the slot, failing while a command buffer in flight
+    /// still reads it
+    fn write(&self, data: T) -> Result<(), WriteLockError> {
+        if self.gpu_reads.compare_exchange(0, CPU_WRITING, Ordering::SeqCst, Ordering::SeqCst).is_err() {
+            return Err(WriteLockError::GpuLocked);
+        }
+
+        // Safe as no command buffer can lock the slot until the write is done,
+        // and the slots don't overlap
+        unsafe {
+            *self.ring.memory.read_write::<T>(self.range()) = data;
+        }
+        self.gpu_reads.store(0, Ordering::SeqCst);
+
+        Ok(())
+    }
+}
+
+unsafe impl<T> DeviceOwned for RingSlot<T> {
+    fn device(&self) -> &Arc<Device> {
+        self.ring.buffer.device()
+    }
+}
+
+unsafe impl<T: Copy + Send + Sync + 'static> BufferAccess for RingSlot<T> {
+    fn inner(&self) -> BufferInner<'_> {
+        BufferInner { buffer: &self.ring.buffer, offset: self.offset }
+    }
+
+    fn size(&self) -> usize {
+        mem::size_of::<T>()
+    }
+
+    fn conflicts_buffer(&self, other: &dyn BufferAccess) -> bool {
+        let other_inner = other.inner();
+        other_inner.buffer.key() == self.ring.buffer.key()
+            && other_inner.offset < self.offset + self.size()
+            && self.offset < other_inner.offset + other.size()
+    }
+
+    fn conflicts_image(&self, _other: &dyn ImageAccess) -> bool {
+        false
+    }
+
+    fn conflict_key(&self) -> (u64, usize) {
+        (self.ring.buffer.key(), self.offset)
+    }
+
+    // The GPU only ever reads uniforms, exclusive accesses are refused
+    fn try_gpu_lock(&self, exclusive_access: bool, _queue: &Queue) -> Result<(), AccessError> {
+        if exclusive_access {
+            return Err(AccessError::AlreadyInUse);
+        }
+
+        let mut reads = self.gpu_reads.load(Ordering::SeqCst);
+        loop {
+            if reads == CPU_WRITING {
+                return Err(AccessError::AlreadyInUse);
+            }
+            match self.gpu_reads.compare_exchange(reads, reads + 1, Ordering::SeqCst, Ordering::SeqCst) {
+                Ok(_) => return Ok(()),
+                Err(current) => reads = current,
+            }
+        }
+    }
+
+    unsafe fn increase_gpu_lock(&self) {
+        let previous = self.gpu_reads.fetch_add(1, Ordering::SeqCst);
+        debug_assert!(previous >= 1 && previous != CPU_WRITING);
+    }
+
+    unsafe fn unlock(&self) {
+        let previous = self.gpu_reads.fetch_sub(1, Ordering::SeqCst);
+        debug_assert!(previous >= 1 && previous != CPU_WRITING);
+    }
+}
+
+unsafe impl<T: Copy + Send + Sync + 'static> TypedBufferAccess for RingSlot<T> {
+    type Content = T;
+}
@@ -0,0 +1,76 @@
+//! Renders the first frame of the default scene offscreen, as `--headless`
+//! does, and compares a hash of its pixels with the one recorded on a
+//! reference machine. Drivers don't rasterize exactly alike, so the golden
+//! hash is only meaningful for the GPU and driver it was recorded with:
+//! record it again with `BLESS_GOLDEN=1 cargo test` after changing either, or
+//! the scene.
+//!
+//! Machines without any Vulkan device, such as most CI runners, skip it, and
+//! so do all of them until a golden hash is recorded.
+
+// Build-in modules
+use std::env;
+use std::fs;
+use std::path::Path;
+
+// External modules
+use testing_rust::config::Config;
+use testing_rust::headless::HeadlessRenderer;
+use vulkano::instance::{Instance, InstanceExtensions, PhysicalDevice};
+
+/// Holds the hash of the first frame, in hexadecimal
+const GOLDEN_PATH: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/tests/golden/headless_quad.hash");
+
+/// Rewrites the golden hash with the one rendered when set
+const BLESS_VAR: &str = "BLESS_GOLDEN";
+
+#[test]
+fn headless_frame_matches_golden_hash() {
+    if !has_vulkan_device() {
+        eprintln!("Skipping, no Vulkan device is available");
+        return;
+    }
+
+    let args: Vec<String> = ["testing-rust", "--headless", "1"].iter().map(|arg| arg.to_string()).collect();
+    let config = Config::from_args(&args).expect("the arguments are valid").expect("--help isn't asked for");
+    let pixels =
+        HeadlessRenderer::new(&config)
+            .and_then(|mut renderer| renderer.draw_frame())
+            .unwrap_or_else(|err| panic!("rendering failed: {}", err));
+
+    let hash = format!("{:016x}", fnv1a(&pixels));
+
+    if env::var_os(BLESS_VAR).is_some() {
+        let golden_dir = Path::new(GOLDEN_PATH).parent().expect("the golden hash is within a directory");
+        fs::create_dir_all(golden_dir).expect("the golden directory can be created");
+        fs::write(GOLDEN_PATH, format!("{}\n", hash)).expect("the golden hash can be written");
+        eprintln!("Recorded the golden hash {}", hash);
+        return;
+    }
+
+    let golden = match fs::read_to_string(GOLDEN_PATH) {
+        Ok(golden) => golden,
+        Err(_) => {
+            eprintln!(
+                "Skipping, no golden hash at {}, record one with {}=1 on the reference machine",
+                GOLDEN_PATH, BLESS_VAR);
+            return;
+        },
+    };
+    assert_eq!(hash, golden.trim(), "the frame differs from the golden one");
+}
+
+/// Whether Vulkan can be loaded and lists at least one device
+fn has_vulkan_device() -> bool {
+    match Instance::new(None, &InstanceExtensions::none(), None) {
+        Ok(instance) => PhysicalDevice::enumerate(&instance).next().is_some(),
+        Err(_) => false,
+    }
+}
+
+/// 64 bits FNV-1a, which unlike the standard library's hasher is guaranteed
+/// to stay the same across Rust releases
+fn fnv1a(bytes: &[u8]) -> u64 {
+    bytes.iter()
+        .fold(0xcbf2_9ce4_8422_2325, |hash, &byte| (hash ^ byte as u64).wrapping_mul(0x0100_0000_01b3))
+}